@@ -1,11 +1,42 @@
 use git2::{ErrorCode, Repository, ResetType};
-use std::{collections::HashMap, env, ffi::OsStr, path::Path, process::Command};
+use std::{
+    collections::HashMap,
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 const GPR2_GIT: &str = "https://github.com/jklmnn/gpr.git";
 const GPR2_REV: &str = "4e88e9734194fc1ad58f19a45c95fa4f17dd475f";
 const GPRCONFIG_KB_GIT: &str = "https://github.com/AdaCore/gprconfig_kb.git";
 const GPRCONFIG_KB_REV: &str = "5a8f26e16ad42f84b4037a7c382b55e5491fbd2c";
 
+/// Directory containing a prebuilt libgpr2c.
+const GPR2C_LIB_DIR: &str = "GPR_RUST_GPR2C_LIB_DIR";
+/// Include directory of a prebuilt libgpr2c.
+const GPR2C_INCLUDE_DIR: &str = "GPR_RUST_GPR2C_INCLUDE_DIR";
+/// Local checkout of the gpr sources, used instead of cloning them.
+const GPR2_SRC_DIR: &str = "GPR_RUST_GPR2_SRC_DIR";
+/// Local checkout of gprconfig_kb, used instead of cloning it.
+const GPRCONFIG_KB_DIR: &str = "GPR_RUST_GPRCONFIG_KB_DIR";
+
+/// Ada libraries libgpr2c depends on, in link order.
+const GPR2C_DEPENDENCIES: [&str; 9] = [
+    "gpr2",
+    "gnatcoll_gmp",
+    "gnatcoll_iconv",
+    "gnatcoll",
+    "xmlada_schema",
+    "xmlada_dom",
+    "xmlada_sax",
+    "xmlada_input_sources",
+    "xmlada_unicode",
+];
+/// Static GNAT runtime libraries, in link order.
+const GNAT_RUNTIME: [&str; 2] = ["gnarl", "gnat"];
+
 fn checkout(url: &str, rev: &str, path: &Path) {
     let path = path.to_str().unwrap();
     let repo = match Repository::clone(url, path) {
@@ -72,9 +103,238 @@ where
     stdout
 }
 
+fn knowledge_base(path: &Path) {
+    println!(
+        "cargo:rustc-env=GPR_RUST_KB_DIR={}",
+        path.join("db").as_path().to_str().unwrap()
+    );
+}
+
+/// Whether the `static` feature is enabled and libgpr2c, its Ada
+/// dependencies and the GNAT runtime are linked statically.
+fn static_linking() -> bool {
+    env::var_os("CARGO_FEATURE_STATIC").is_some()
+}
+
+/// Collects the directories of the static archives of the libgpr2c
+/// dependencies below `dir`. Archives in a `static-pic` directory are
+/// preferred over those built without `-fPIC`.
+fn archives(dir: &Path, found: &mut HashMap<String, PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok).map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            archives(&path, found);
+            continue;
+        }
+        let Some(name) = path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .and_then(|f| f.strip_prefix("lib"))
+            .and_then(|f| f.strip_suffix(".a"))
+        else {
+            continue;
+        };
+        if !GPR2C_DEPENDENCIES.contains(&name) {
+            continue;
+        }
+        if dir.ends_with("static-pic") || !found.contains_key(name) {
+            found.insert(name.to_owned(), dir.to_path_buf());
+        }
+    }
+}
+
+/// Links the static archives of the Ada dependencies of libgpr2c found below
+/// `roots` and the static GNAT runtime of the compiler in `PATH`.
+fn link_static_dependencies(roots: &[PathBuf], envs: &HashMap<String, String>) {
+    let mut found = HashMap::new();
+    for root in roots {
+        archives(root, &mut found);
+    }
+    for name in GPR2C_DEPENDENCIES {
+        let dir = found
+            .get(name)
+            .unwrap_or_else(|| panic!("failed to find lib{name}.a for static linking"));
+        println!("cargo:rustc-link-search=native={}", dir.to_str().unwrap());
+        println!("cargo:rustc-link-lib=static={name}");
+    }
+    let gnatls = call("gnatls", envs, None, ["-v"], true);
+    let adalib = gnatls
+        .lines()
+        .map(str::trim)
+        .find(|line| line.ends_with("adalib") || line.ends_with("adalib/"))
+        .unwrap_or_else(|| panic!("failed to find the GNAT runtime with gnatls"));
+    println!("cargo:rustc-link-search=native={adalib}");
+    for library in GNAT_RUNTIME {
+        println!("cargo:rustc-link-lib=static={library}");
+    }
+    for library in ["gmp", "dl", "pthread"] {
+        println!("cargo:rustc-link-lib=dylib={library}");
+    }
+}
+
+/// Links libgpr2c from `lib_dir`, either dynamically or, with the `static`
+/// feature, statically together with all its dependencies found below
+/// `roots`.
+fn link_gpr2c(lib_dir: &Path, roots: &[PathBuf], envs: &HashMap<String, String>) {
+    println!(
+        "cargo:rustc-link-search=native={}",
+        lib_dir.to_str().unwrap()
+    );
+    if !static_linking() {
+        println!("cargo:rustc-link-lib=dylib=gpr2c");
+        return;
+    }
+    println!("cargo:rustc-link-lib=static=gpr2c");
+    link_static_dependencies(roots, envs);
+}
+
+/// Directories listed in the `GPR_PROJECT_PATH` of `envs`.
+fn project_dirs(envs: &HashMap<String, String>) -> Vec<PathBuf> {
+    envs.get("GPR_PROJECT_PATH")
+        .map(|path| env::split_paths(path).collect())
+        .unwrap_or_default()
+}
+
+/// Links a libgpr2c that has been built outside of cargo. For static linking
+/// the archives of its dependencies are searched in `GPR_PROJECT_PATH`.
+fn prebuilt() -> bool {
+    let Some(lib_dir) = env::var_os(GPR2C_LIB_DIR) else {
+        return false;
+    };
+    if let Some(include_dir) = env::var_os(GPR2C_INCLUDE_DIR) {
+        println!(
+            "cargo:include={}",
+            Path::new(&include_dir).to_str().unwrap()
+        );
+    }
+    let envs: HashMap<String, String> = env::vars().collect();
+    link_gpr2c(Path::new(&lib_dir), &project_dirs(&envs), &envs);
+    true
+}
+
+/// Links a libgpr2c installed on the system with a pkg-config file.
+fn system() -> bool {
+    match pkg_config::Config::new()
+        .statik(static_linking())
+        .probe("gpr2c")
+    {
+        Ok(library) => {
+            if let Some(include_dir) = library.include_paths.first() {
+                println!("cargo:include={}", include_dir.to_str().unwrap());
+            }
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Builds libgpr2 and the C binding from the sources in `gpr_path`. All Ada
+/// dependencies have to be available through `GPR_PROJECT_PATH` in `envs`.
+fn build_gpr2c(gpr_path: &Path, gprconfig_kb_path: &Path, envs: &mut HashMap<String, String>) {
+    let mut gprconfig_db_path = String::from("GPR2KBDIR=");
+    gprconfig_db_path.push_str(gprconfig_kb_path.join("db").as_path().to_str().unwrap());
+    let project_path = envs.entry(String::from("GPR_PROJECT_PATH")).or_default();
+    *project_path = if project_path.is_empty() {
+        gpr_path.to_str().unwrap().to_owned()
+    } else {
+        format!("{}:{}", gpr_path.to_str().unwrap(), project_path)
+    };
+    let _ = call(
+        "make",
+        &*envs,
+        None,
+        [
+            "-C",
+            gpr_path.to_str().unwrap(),
+            gprconfig_db_path.as_str(),
+            "build-lib-static-pic",
+        ],
+        true,
+    );
+    let gpr2c_path = gpr_path.join("bindings").join("c");
+    let _ = call(
+        "gprbuild",
+        &*envs,
+        Some(gpr_path),
+        [
+            "-j0",
+            "-p",
+            "-P",
+            gpr2c_path
+                .join("gpr2_c_binding.gpr")
+                .as_path()
+                .to_str()
+                .unwrap(),
+            "-XGPR2_BUILD=release",
+        ]
+        .into_iter()
+        .chain(static_linking().then_some("-XLIBRARY_TYPE=static-pic"))
+        .chain(["-cargs", "-fPIC"]),
+        true,
+    );
+    let mut roots = vec![gpr_path.join("build")];
+    roots.extend(project_dirs(envs));
+    link_gpr2c(
+        &gpr2c_path.join("build").join("release").join("lib"),
+        &roots,
+        envs,
+    );
+}
+
+/// Builds libgpr2c from local source directories without network access.
+fn vendored() -> bool {
+    let Some(gpr_path) = env::var_os(GPR2_SRC_DIR) else {
+        return false;
+    };
+    let gprconfig_kb_path = env::var_os(GPRCONFIG_KB_DIR)
+        .unwrap_or_else(|| panic!("{GPRCONFIG_KB_DIR} must be set together with {GPR2_SRC_DIR}"));
+    let mut envs: HashMap<String, String> = env::vars().collect();
+    build_gpr2c(
+        Path::new(&gpr_path),
+        Path::new(&gprconfig_kb_path),
+        &mut envs,
+    );
+    true
+}
+
+/// Whether libgpr2c is replaced by a stub, so the crate can be built and
+/// documented without an Ada toolchain.
+fn stub() -> bool {
+    ["DOCS_RS", "CARGO_FEATURE_STUB"]
+        .iter()
+        .any(|var| env::var_os(var).is_some())
+}
+
 fn main() {
-    let out_dir = env::var("OUT_DIR").unwrap();
+    println!("cargo::rustc-check-cfg=cfg(gpr2_stub)");
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+    if stub() {
+        println!("cargo:rustc-cfg=gpr2_stub");
+        return;
+    }
     println!("cargo:rerun-if-env-changed=PATH");
+    println!("cargo:rerun-if-env-changed=GPR_PROJECT_PATH");
+    for var in [
+        GPR2C_LIB_DIR,
+        GPR2C_INCLUDE_DIR,
+        GPR2_SRC_DIR,
+        GPRCONFIG_KB_DIR,
+    ] {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
+    if let Some(kb) = env::var_os(GPRCONFIG_KB_DIR) {
+        knowledge_base(Path::new(&kb));
+    }
+    // Explicitly configured sources come before a libgpr2c found on the
+    // system.
+    if prebuilt() || vendored() || system() {
+        return;
+    }
+    let out_dir = env::var("OUT_DIR").unwrap();
     let out_dir = Path::new(&out_dir);
     let contrib = out_dir.join("contrib");
     let gpr_path = contrib.join("gpr");
@@ -82,13 +342,16 @@ fn main() {
         "cargo:rerun-if-changed={}",
         contrib.as_path().to_str().unwrap()
     );
-    let gprconfig_kb_path = contrib.join("gprconfig_kb");
     checkout(GPR2_GIT, GPR2_REV, gpr_path.as_path());
-    checkout(
-        GPRCONFIG_KB_GIT,
-        GPRCONFIG_KB_REV,
-        gprconfig_kb_path.as_path(),
-    );
+    let gprconfig_kb_path = match env::var_os(GPRCONFIG_KB_DIR) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = contrib.join("gprconfig_kb");
+            checkout(GPRCONFIG_KB_GIT, GPRCONFIG_KB_REV, path.as_path());
+            knowledge_base(&path);
+            path
+        }
+    };
     let mut envs: HashMap<String, String> = env::vars()
         .filter(|e| !e.0.ends_with("ALIRE_PREFIX"))
         .collect();
@@ -157,69 +420,5 @@ fn main() {
             }
         }
     }
-    let mut gprconfig_db_path = String::from("GPR2KBDIR=");
-    gprconfig_db_path.push_str(gprconfig_kb_path.join("db").as_path().to_str().unwrap());
-    let mut gpr_project_path = String::new();
-    if let Ok(gpp) = env::var("GPR_PROJECT_PATH") {
-        gpr_project_path.push_str(gpp.as_str());
-        envs.get_mut("GPR_PROJECT_PATH").unwrap().push(':');
-    }
-    gpr_project_path.push(':');
-    gpr_project_path.push_str(gpr_path.to_str().unwrap());
-    envs.get_mut("GPR_PROJECT_PATH").unwrap().push(':');
-    envs.get_mut("GPR_PROJECT_PATH")
-        .unwrap()
-        .push_str(&gpr_project_path);
-    let _ = call(
-        "make",
-        &envs,
-        None,
-        [
-            "-C",
-            gpr_path.to_str().unwrap(),
-            gprconfig_db_path.as_str(),
-            "build-lib-static-pic",
-        ],
-        true,
-    );
-    let gpr2c_path = gpr_path.join("bindings").join("c");
-    let mut gpr_project_path = gpr_path.as_path().to_str().unwrap().to_owned();
-    gpr_project_path.push(':');
-    if let Ok(gpp) = env::var("GPR_PROJECT_PATH") {
-        gpr_project_path.push_str(gpp.as_str());
-        envs.get_mut("GPR_PROJECT_PATH").unwrap().push(':');
-        envs.get_mut("GPR_PROJECT_PATH")
-            .unwrap()
-            .push_str(&gpr_project_path);
-    }
-    let _ = call(
-        "gprbuild",
-        &envs,
-        Some(&gpr_path),
-        [
-            "-j0",
-            "-p",
-            "-P",
-            gpr2c_path
-                .join("gpr2_c_binding.gpr")
-                .as_path()
-                .to_str()
-                .unwrap(),
-            "-XGPR2_BUILD=release",
-            "-cargs",
-            "-fPIC",
-        ],
-        true,
-    );
-    println!(
-        "cargo:rustc-link-search={}",
-        gpr2c_path
-            .join("build")
-            .join("release")
-            .join("lib")
-            .as_path()
-            .to_str()
-            .unwrap()
-    );
-    println!("cargo:rustc-link-lib=dylib=gpr2c");
+    build_gpr2c(&gpr_path, &gprconfig_kb_path, &mut envs);
 }
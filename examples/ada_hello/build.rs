@@ -1,4 +1,4 @@
-use gpr::Project;
+use gpr::{LinkOptions, Project, Rpath};
 use std::{
     path::Path,
     process::{Command, Stdio},
@@ -16,21 +16,18 @@ fn main() {
         panic!();
     }
 
-    println!(
-        "cargo:rustc-link-search={}",
-        ada_hello.library_dir().unwrap().to_str().unwrap()
-    );
     println!(
         "cargo:rerun-if-changed={}",
-        ada_hello.source_dirs().unwrap()[0].as_str()
+        ada_hello.source_dirs().unwrap()[0].display()
     );
     println!(
         "cargo:rerun-if-changed={}",
         ada_hello.library_dir().unwrap().to_str().unwrap()
     );
-    println!(
-        "cargo:rustc-link-lib={}={}",
-        ada_hello.library_kind().unwrap(),
-        ada_hello.library_name().unwrap()
-    );
+    for directive in ada_hello
+        .link_directives(&LinkOptions::new().rpath(Rpath::Absolute))
+        .unwrap()
+    {
+        println!("{directive}");
+    }
 }
@@ -0,0 +1,895 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::{self, Write},
+    path::Path,
+};
+
+use syn::{punctuated::Punctuated, spanned::Spanned};
+
+use super::{error, gprgen::ada_name};
+
+/// Rust primitive and `core::ffi` types and their Ada equivalent.
+const PRIMITIVES: &[(&str, &str)] = &[
+    ("i8", "Interfaces.Integer_8"),
+    ("i16", "Interfaces.Integer_16"),
+    ("i32", "Interfaces.Integer_32"),
+    ("i64", "Interfaces.Integer_64"),
+    ("u8", "Interfaces.Unsigned_8"),
+    ("u16", "Interfaces.Unsigned_16"),
+    ("u32", "Interfaces.Unsigned_32"),
+    ("u64", "Interfaces.Unsigned_64"),
+    ("f32", "Interfaces.C.C_float"),
+    ("f64", "Interfaces.C.double"),
+    ("bool", "Interfaces.C.C_bool"),
+    ("usize", "Interfaces.C.size_t"),
+    ("isize", "Interfaces.C.ptrdiff_t"),
+    ("c_int", "Interfaces.C.int"),
+    ("c_uint", "Interfaces.C.unsigned"),
+    ("c_short", "Interfaces.C.short"),
+    ("c_ushort", "Interfaces.C.unsigned_short"),
+    ("c_long", "Interfaces.C.long"),
+    ("c_ulong", "Interfaces.C.unsigned_long"),
+    ("c_longlong", "Interfaces.C.long_long"),
+    ("c_ulonglong", "Interfaces.C.unsigned_long_long"),
+    ("c_char", "Interfaces.C.char"),
+    ("c_schar", "Interfaces.C.signed_char"),
+    ("c_uchar", "Interfaces.C.unsigned_char"),
+    ("c_float", "Interfaces.C.C_float"),
+    ("c_double", "Interfaces.C.double"),
+];
+
+const ADA_KEYWORDS: &[&str] = &[
+    "abort",
+    "abs",
+    "abstract",
+    "accept",
+    "access",
+    "aliased",
+    "all",
+    "and",
+    "array",
+    "at",
+    "begin",
+    "body",
+    "case",
+    "constant",
+    "declare",
+    "delay",
+    "delta",
+    "digits",
+    "do",
+    "else",
+    "elsif",
+    "end",
+    "entry",
+    "exception",
+    "exit",
+    "for",
+    "function",
+    "generic",
+    "goto",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "limited",
+    "loop",
+    "mod",
+    "new",
+    "not",
+    "null",
+    "of",
+    "or",
+    "others",
+    "out",
+    "overriding",
+    "package",
+    "parallel",
+    "pragma",
+    "private",
+    "procedure",
+    "protected",
+    "raise",
+    "range",
+    "record",
+    "rem",
+    "renames",
+    "requeue",
+    "return",
+    "reverse",
+    "select",
+    "separate",
+    "some",
+    "subtype",
+    "synchronized",
+    "tagged",
+    "task",
+    "terminate",
+    "then",
+    "type",
+    "until",
+    "use",
+    "when",
+    "while",
+    "with",
+    "xor",
+];
+
+/// Checks whether `name` is a valid, possibly qualified, Ada unit name.
+fn is_unit_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        part.starts_with(|c: char| c.is_ascii_alphabetic())
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !part.ends_with('_')
+            && !part.contains("__")
+            && !ADA_KEYWORDS.contains(&part.to_lowercase().as_str())
+    })
+}
+
+fn line(node: &impl Spanned) -> usize {
+    node.span().start().line
+}
+
+fn docs(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Arguments of all `#[repr(...)]` attributes.
+fn reprs(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .filter_map(|meta| meta.path().get_ident().map(ToString::to_string))
+        .collect()
+}
+
+/// Link name of a function exported with `#[no_mangle]` or
+/// `#[export_name = "..."]`, both optionally wrapped in `unsafe(...)`.
+fn export_name(function: &syn::ItemFn) -> Option<String> {
+    function.attrs.iter().find_map(|attr| {
+        let meta = match &attr.meta {
+            syn::Meta::List(list) if list.path.is_ident("unsafe") => list.parse_args().ok()?,
+            meta => meta.clone(),
+        };
+        match meta {
+            syn::Meta::Path(path) if path.is_ident("no_mangle") => {
+                Some(function.sig.ident.to_string())
+            }
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(name),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("export_name") => Some(name.value()),
+            _ => None,
+        }
+    })
+}
+
+fn discriminant(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(value),
+            ..
+        }) => value.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => discriminant(expr).map(|value| -value),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => discriminant(expr),
+        _ => None,
+    }
+}
+
+enum TypeDef {
+    Record(Vec<(String, syn::Type)>),
+    Enum {
+        literals: Vec<(String, i128)>,
+        size: Option<u32>,
+    },
+    Alias(Box<syn::Type>),
+    /// Type without a C representation, it can only be passed by pointer.
+    Opaque,
+}
+
+struct TypeDecl {
+    def: TypeDef,
+    docs: Vec<String>,
+    line: usize,
+}
+
+struct Function {
+    link_name: String,
+    name: String,
+    params: Vec<(Option<String>, syn::Type)>,
+    result: Option<syn::Type>,
+    docs: Vec<String>,
+    line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AdaType {
+    Named(String),
+    Access {
+        not_null: bool,
+        constant: bool,
+        target: String,
+    },
+}
+
+impl fmt::Display for AdaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdaType::Named(name) => write!(f, "{name}"),
+            AdaType::Access {
+                not_null,
+                constant,
+                target,
+            } => {
+                if *not_null {
+                    write!(f, "not null ")?;
+                }
+                write!(f, "access ")?;
+                if *constant {
+                    write!(f, "constant ")?;
+                }
+                write!(f, "{target}")
+            }
+        }
+    }
+}
+
+/// Collects the exported functions and the types of a Rust source file,
+/// including inline modules.
+fn collect(
+    items: &[syn::Item],
+    file: &Path,
+    types: &mut HashMap<String, TypeDecl>,
+    functions: &mut Vec<Function>,
+) -> Result<(), error::Error> {
+    for item in items {
+        match item {
+            syn::Item::Fn(function) => {
+                let Some(link_name) = export_name(function) else {
+                    continue;
+                };
+                let sig = &function.sig;
+                let c_abi = sig
+                    .abi
+                    .as_ref()
+                    .is_some_and(|abi| abi.name.as_ref().is_none_or(|n| n.value() == "C"));
+                if !c_abi {
+                    continue;
+                }
+                if sig.variadic.is_some() || !sig.generics.params.is_empty() {
+                    return Err(error::Error::binding(
+                        file,
+                        line(sig),
+                        &format!("function {} cannot be mapped to Ada", sig.ident),
+                    ));
+                }
+                let params = sig
+                    .inputs
+                    .iter()
+                    .map(|input| match input {
+                        syn::FnArg::Typed(param) => Ok((
+                            match param.pat.as_ref() {
+                                syn::Pat::Ident(pat) => Some(pat.ident.to_string()),
+                                _ => None,
+                            },
+                            (*param.ty).clone(),
+                        )),
+                        syn::FnArg::Receiver(receiver) => Err(error::Error::binding(
+                            file,
+                            line(receiver),
+                            "methods cannot be exported",
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+                functions.push(Function {
+                    link_name,
+                    name: sig.ident.to_string(),
+                    params,
+                    result: match &sig.output {
+                        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+                            syn::Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+                            ty => Some(ty.clone()),
+                        },
+                        syn::ReturnType::Default => None,
+                    },
+                    docs: docs(&function.attrs),
+                    line: line(sig),
+                });
+            }
+            syn::Item::Struct(item) => {
+                let def = match &item.fields {
+                    syn::Fields::Named(fields)
+                        if reprs(&item.attrs).iter().any(|r| r == "C")
+                            && item.generics.params.is_empty() =>
+                    {
+                        TypeDef::Record(
+                            fields
+                                .named
+                                .iter()
+                                .filter_map(|f| Some((f.ident.as_ref()?.to_string(), f.ty.clone())))
+                                .collect(),
+                        )
+                    }
+                    _ => TypeDef::Opaque,
+                };
+                types.insert(
+                    item.ident.to_string(),
+                    TypeDecl {
+                        def,
+                        docs: docs(&item.attrs),
+                        line: line(&item.ident),
+                    },
+                );
+            }
+            syn::Item::Enum(item) => {
+                let reprs = reprs(&item.attrs);
+                let size = reprs.iter().find_map(|r| {
+                    r.strip_prefix(['i', 'u'])
+                        .and_then(|bits| bits.parse::<u32>().ok())
+                });
+                let mut literals = Vec::new();
+                let mut next = 0;
+                for variant in &item.variants {
+                    if !matches!(variant.fields, syn::Fields::Unit) {
+                        literals.clear();
+                        break;
+                    }
+                    if let Some((_, expr)) = &variant.discriminant {
+                        next = discriminant(expr).ok_or_else(|| {
+                            error::Error::binding(
+                                file,
+                                line(expr),
+                                &format!(
+                                    "discriminant of {}::{} must be an integer literal",
+                                    item.ident, variant.ident
+                                ),
+                            )
+                        })?;
+                    }
+                    literals.push((variant.ident.to_string(), next));
+                    next += 1;
+                }
+                let def =
+                    if !literals.is_empty() && (size.is_some() || reprs.iter().any(|r| r == "C")) {
+                        TypeDef::Enum { literals, size }
+                    } else {
+                        TypeDef::Opaque
+                    };
+                types.insert(
+                    item.ident.to_string(),
+                    TypeDecl {
+                        def,
+                        docs: docs(&item.attrs),
+                        line: line(&item.ident),
+                    },
+                );
+            }
+            syn::Item::Type(item) if item.generics.params.is_empty() => {
+                types.insert(
+                    item.ident.to_string(),
+                    TypeDecl {
+                        def: TypeDef::Alias(item.ty.clone()),
+                        docs: docs(&item.attrs),
+                        line: line(&item.ident),
+                    },
+                );
+            }
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect(items, file, types, functions)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+struct Generator<'s> {
+    file: &'s Path,
+    types: &'s HashMap<String, TypeDecl>,
+    /// Lower case Ada names of all local types.
+    type_names: HashSet<String>,
+    withs: BTreeSet<String>,
+    /// Types referenced through pointers which get an incomplete declaration.
+    incomplete: BTreeSet<String>,
+    visiting: HashSet<String>,
+    declared: HashSet<String>,
+    declarations: Vec<String>,
+}
+
+impl<'s> Generator<'s> {
+    fn value_name(&self, name: &str) -> String {
+        let ada = ada_name(name);
+        let lower = ada.to_lowercase();
+        if ADA_KEYWORDS.contains(&lower.as_str()) || self.type_names.contains(&lower) {
+            format!("{ada}_Rust")
+        } else {
+            ada
+        }
+    }
+
+    fn type_name(&self, name: &str) -> String {
+        let ada = ada_name(name);
+        if ADA_KEYWORDS.contains(&ada.to_lowercase().as_str()) {
+            format!("{ada}_Rust")
+        } else {
+            ada
+        }
+    }
+
+    fn primitive(&mut self, name: &str) -> Option<String> {
+        let (_, ada) = PRIMITIVES.iter().find(|(rust, _)| *rust == name)?;
+        let (package, _) = ada.rsplit_once('.')?;
+        self.withs.insert(package.to_string());
+        Some(ada.to_string())
+    }
+
+    fn unsupported(&self, ty: &syn::Type) -> error::Error {
+        error::Error::binding(
+            self.file,
+            line(ty),
+            &format!(
+                "type {} cannot be mapped to Ada",
+                quote::ToTokens::to_token_stream(ty)
+            ),
+        )
+    }
+
+    /// Name of the last path segment if `ty` is a plain path without generic
+    /// arguments.
+    fn path_name(ty: &syn::Type) -> Option<(String, bool)> {
+        match ty {
+            syn::Type::Paren(ty) => Generator::path_name(&ty.elem),
+            syn::Type::Group(ty) => Generator::path_name(&ty.elem),
+            syn::Type::Path(ty) if ty.qself.is_none() => {
+                let segment = ty.path.segments.last()?;
+                if !segment.arguments.is_none() {
+                    return None;
+                }
+                Some((segment.ident.to_string(), ty.path.segments.len() == 1))
+            }
+            _ => None,
+        }
+    }
+
+    fn map(&mut self, ty: &syn::Type) -> Result<AdaType, error::Error> {
+        match ty {
+            syn::Type::Paren(inner) => self.map(&inner.elem),
+            syn::Type::Group(inner) => self.map(&inner.elem),
+            syn::Type::Ptr(ptr) => self.access(ty, &ptr.elem, ptr.mutability.is_none(), false),
+            syn::Type::Reference(reference) => {
+                self.access(ty, &reference.elem, reference.mutability.is_none(), true)
+            }
+            _ => {
+                let (name, local) = Generator::path_name(ty).ok_or_else(|| self.unsupported(ty))?;
+                if local && self.types.contains_key(&name) {
+                    if matches!(self.types[&name].def, TypeDef::Opaque) {
+                        return Err(error::Error::binding(
+                            self.file,
+                            line(ty),
+                            &format!("type {name} does not have a C representation"),
+                        ));
+                    }
+                    self.declare(&name)?;
+                    return Ok(AdaType::Named(self.type_name(&name)));
+                }
+                self.primitive(&name)
+                    .map(AdaType::Named)
+                    .ok_or_else(|| self.unsupported(ty))
+            }
+        }
+    }
+
+    fn access(
+        &mut self,
+        ty: &syn::Type,
+        target: &syn::Type,
+        constant: bool,
+        not_null: bool,
+    ) -> Result<AdaType, error::Error> {
+        let (name, local) = Generator::path_name(target).ok_or_else(|| self.unsupported(ty))?;
+        let address = || AdaType::Named(String::from("System.Address"));
+        let types = self.types;
+        if local && types.contains_key(&name) {
+            let target = match types[&name].def {
+                TypeDef::Opaque => {
+                    self.withs.insert(String::from("System"));
+                    return Ok(address());
+                }
+                TypeDef::Alias(_) => {
+                    self.declare(&name)?;
+                    self.type_name(&name)
+                }
+                TypeDef::Record(_) | TypeDef::Enum { .. } => {
+                    let target = self.type_name(&name);
+                    self.incomplete.insert(target.clone());
+                    self.declare(&name)?;
+                    target
+                }
+            };
+            return Ok(AdaType::Access {
+                not_null,
+                constant,
+                target,
+            });
+        }
+        match name.as_str() {
+            "c_char" if !not_null => {
+                self.withs.insert(String::from("Interfaces.C.Strings"));
+                Ok(AdaType::Named(String::from(
+                    "Interfaces.C.Strings.chars_ptr",
+                )))
+            }
+            "c_void" => {
+                self.withs.insert(String::from("System"));
+                Ok(address())
+            }
+            _ => Ok(AdaType::Access {
+                not_null,
+                constant,
+                target: self.primitive(&name).ok_or_else(|| self.unsupported(ty))?,
+            }),
+        }
+    }
+
+    /// Adds the declaration of the local type `name` after the declarations
+    /// of all types it depends on.
+    fn declare(&mut self, name: &str) -> Result<(), error::Error> {
+        if self.declared.contains(name) || !self.visiting.insert(name.to_string()) {
+            return Ok(());
+        }
+        let types = self.types;
+        let decl = &types[name];
+        let ada = self.type_name(name);
+        let mut code = String::new();
+        for doc in &decl.docs {
+            let _ = writeln!(code, "   --  {doc}");
+        }
+        match &decl.def {
+            TypeDef::Record(fields) => {
+                if fields.is_empty() {
+                    let _ = writeln!(code, "   type {ada} is null record");
+                } else {
+                    let _ = writeln!(code, "   type {ada} is record");
+                    for (field, ty) in fields {
+                        let ty = self.map(ty)?;
+                        let _ = writeln!(code, "      {} : {ty};", self.value_name(field));
+                    }
+                    let _ = writeln!(code, "   end record");
+                }
+                let _ = writeln!(code, "     with Convention => C_Pass_By_Copy;");
+            }
+            TypeDef::Enum { literals, size } => {
+                if literals.windows(2).any(|w| w[0].1 >= w[1].1) {
+                    return Err(error::Error::binding(
+                        self.file,
+                        decl.line,
+                        &format!("discriminants of enum {name} must be ascending"),
+                    ));
+                }
+                let names: Vec<String> = literals.iter().map(|(l, _)| self.type_name(l)).collect();
+                let _ = writeln!(code, "   type {ada} is ({})", names.join(", "));
+                match size {
+                    Some(size) => {
+                        let _ = writeln!(code, "     with Size => {size};");
+                    }
+                    None => {
+                        let _ = writeln!(code, "     with Convention => C;");
+                    }
+                }
+                if literals
+                    .iter()
+                    .enumerate()
+                    .any(|(i, (_, v))| *v != i as i128)
+                {
+                    let values: Vec<String> = names
+                        .iter()
+                        .zip(literals)
+                        .map(|(n, (_, v))| format!("{n} => {v}"))
+                        .collect();
+                    let _ = writeln!(code, "   for {ada} use ({});", values.join(", "));
+                }
+            }
+            TypeDef::Alias(ty) => match self.map(ty)? {
+                AdaType::Named(target) => {
+                    let _ = writeln!(code, "   subtype {ada} is {target};");
+                }
+                AdaType::Access {
+                    not_null,
+                    constant,
+                    target,
+                } => {
+                    let _ = writeln!(
+                        code,
+                        "   type {ada} is {}access {} {target}\n     with Convention => C;",
+                        if not_null { "not null " } else { "" },
+                        if constant { "constant" } else { "all" }
+                    );
+                }
+            },
+            TypeDef::Opaque => unreachable!("opaque types are never declared"),
+        }
+        self.visiting.remove(name);
+        self.declared.insert(name.to_string());
+        self.declarations.push(code);
+        Ok(())
+    }
+
+    fn function(&mut self, function: &Function) -> Result<String, error::Error> {
+        let name = self.type_name(&function.name);
+        if self.type_names.contains(&name.to_lowercase()) {
+            return Err(error::Error::binding(
+                self.file,
+                function.line,
+                &format!(
+                    "function {} clashes with a type of the same name",
+                    function.name
+                ),
+            ));
+        }
+        let mut params = Vec::new();
+        for (index, (param, ty)) in function.params.iter().enumerate() {
+            let param = match param {
+                Some(param) if !ada_name(param).is_empty() => self.value_name(param),
+                _ => format!("Arg_{}", index + 1),
+            };
+            params.push(format!("{param} : {}", self.map(ty)?));
+        }
+        let mut code = String::new();
+        for doc in &function.docs {
+            let _ = writeln!(code, "   --  {doc}");
+        }
+        let _ = write!(
+            code,
+            "   {} {name}",
+            if function.result.is_some() {
+                "function"
+            } else {
+                "procedure"
+            }
+        );
+        if !params.is_empty() {
+            let _ = write!(code, " ({})", params.join("; "));
+        }
+        if let Some(result) = &function.result {
+            let _ = write!(code, " return {}", self.map(result)?);
+        }
+        let _ = writeln!(
+            code,
+            "\n     with Import, Convention => C, External_Name => \"{}\";",
+            function.link_name
+        );
+        Ok(code)
+    }
+}
+
+/// Generates an Ada package spec `package` that imports all `extern "C"`
+/// functions exported by the Rust source `source` together with the
+/// `#[repr(C)]` types they use.
+pub(crate) fn generate(source: &str, file: &Path, package: &str) -> Result<String, error::Error> {
+    if !is_unit_name(package) {
+        return Err(error::Error::binding(
+            file,
+            0,
+            &format!("{package} is not a valid Ada package name"),
+        ));
+    }
+    let syntax = syn::parse_file(source)
+        .map_err(|e| error::Error::binding(file, e.span().start().line, &e.to_string()))?;
+    let mut types = HashMap::new();
+    let mut functions = Vec::new();
+    collect(&syntax.items, file, &mut types, &mut functions)?;
+    let mut generator = Generator {
+        file,
+        types: &types,
+        type_names: HashSet::new(),
+        withs: BTreeSet::new(),
+        incomplete: BTreeSet::new(),
+        visiting: HashSet::new(),
+        declared: HashSet::new(),
+        declarations: Vec::new(),
+    };
+    generator.type_names = types
+        .keys()
+        .map(|name| generator.type_name(name).to_lowercase())
+        .collect();
+    let mut subprograms = Vec::new();
+    for function in &functions {
+        subprograms.push(generator.function(function)?);
+    }
+
+    let file_name = file.file_name().unwrap_or(file.as_os_str());
+    let mut code = format!(
+        "--  Bindings for the Rust functions of {}, generated by gpr.\n\n",
+        file_name.to_string_lossy()
+    );
+    for with in &generator.withs {
+        let _ = writeln!(code, "with {with};");
+    }
+    if !generator.withs.is_empty() {
+        code.push('\n');
+    }
+    let _ = writeln!(code, "package {package} is\n");
+    for incomplete in &generator.incomplete {
+        let _ = writeln!(code, "   type {incomplete};");
+    }
+    if !generator.incomplete.is_empty() {
+        code.push('\n');
+    }
+    for declaration in generator.declarations.iter().chain(&subprograms) {
+        code.push_str(declaration);
+        code.push('\n');
+    }
+    let _ = writeln!(code, "end {package};");
+    Ok(code)
+}
+
+/// File name of the spec of `package` according to the default GNAT naming
+/// scheme.
+pub(crate) fn spec_file(package: &str) -> String {
+    format!("{}.ads", package.to_lowercase().replace('.', "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(source: &str) -> Result<String, error::Error> {
+        generate(source, Path::new("lib.rs"), "Rust_Lib")
+    }
+
+    #[test]
+    fn test_functions() {
+        let code = spec(
+            "/// Adds two numbers.
+            #[no_mangle]
+            pub extern \"C\" fn rust_add(a: core::ffi::c_int, b: core::ffi::c_int) -> core::ffi::c_int {
+                a + b
+            }
+
+            #[unsafe(export_name = \"rust_log\")]
+            pub unsafe extern \"C\" fn log(message: *const c_char, _: usize) {}
+
+            pub extern \"C\" fn not_exported() {}
+
+            #[no_mangle]
+            pub fn not_c() {}",
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            "--  Bindings for the Rust functions of lib.rs, generated by gpr.
+
+with Interfaces.C;
+with Interfaces.C.Strings;
+
+package Rust_Lib is
+
+   --  Adds two numbers.
+   function Rust_Add (A : Interfaces.C.int; B : Interfaces.C.int) return Interfaces.C.int
+     with Import, Convention => C, External_Name => \"rust_add\";
+
+   procedure Log (Message : Interfaces.C.Strings.chars_ptr; Arg_2 : Interfaces.C.size_t)
+     with Import, Convention => C, External_Name => \"rust_log\";
+
+end Rust_Lib;
+"
+        );
+    }
+
+    #[test]
+    fn test_types() {
+        let code = spec(
+            "#[repr(C)]
+            pub struct Node {
+                pub value: Point,
+                pub next: *mut Node,
+            }
+
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct Point {
+                pub x: i32,
+                pub r#type: Color,
+            }
+
+            #[repr(u8)]
+            pub enum Color { Red = 1, Green, Blue = 4 }
+
+            pub type Handle = *mut Context;
+            pub struct Context { inner: Vec<u8> }
+            pub type Unused = u32;
+
+            #[no_mangle]
+            pub extern \"C\" fn first(node: &Node, point: *mut Point, context: Handle) -> bool {
+                true
+            }",
+        )
+        .unwrap();
+        assert!(code.contains("with Interfaces;\nwith Interfaces.C;\nwith System;\n"));
+        assert!(code.contains("   type Node;\n   type Point;\n\n"));
+        assert!(code.contains(
+            "   type Color is (Red, Green, Blue)
+     with Size => 8;
+   for Color use (Red => 1, Green => 2, Blue => 4);
+
+   type Point is record
+      X : Interfaces.Integer_32;
+      Type_Rust : Color;
+   end record
+     with Convention => C_Pass_By_Copy;
+
+   type Node is record
+      Value : Point;
+      Next : access Node;
+   end record
+     with Convention => C_Pass_By_Copy;
+
+   subtype Handle is System.Address;
+
+   function First (Node_Rust : not null access constant Node; Point_Rust : access Point; Context_Rust : Handle) return Interfaces.C.C_bool"
+        ));
+        assert!(!code.contains("Unused"));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let err = spec(
+            "#[no_mangle]
+            pub extern \"C\" fn f(v: Vec<u8>) {}",
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "lib.rs:2: type Vec < u8 > cannot be mapped to Ada"
+        );
+        let err = spec(
+            "struct S { x: i32 }
+            #[no_mangle]
+            pub extern \"C\" fn f(s: S) {}",
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "lib.rs:3: type S does not have a C representation"
+        );
+        assert!(generate("", Path::new("lib.rs"), "Rust.Package").is_err());
+        assert!(spec("fn (").is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(spec_file("Rust.Lib"), "rust-lib.ads");
+        assert!(is_unit_name("Rust.Lib"));
+        assert!(!is_unit_name("Rust__Lib"));
+    }
+}
@@ -0,0 +1,156 @@
+/// Location of a syntax element in the source text. `start` and `end` are
+/// byte offsets, `line` and `column` refer to the start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Possibly qualified name such as `Common.Mode` or `Compiler`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Name {
+    pub parts: Vec<String>,
+    pub span: Span,
+}
+
+impl Name {
+    pub fn last(&self) -> &str {
+        self.parts.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Qualifier {
+    Abstract,
+    Standard,
+    Library,
+    Aggregate,
+    AggregateLibrary,
+    Configuration,
+}
+
+/// String literal together with its location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Literal {
+    pub value: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct With {
+    pub limited: bool,
+    pub paths: Vec<Literal>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Extends {
+    pub all: bool,
+    pub path: Literal,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ProjectFile {
+    pub withs: Vec<With>,
+    pub qualifier: Option<Qualifier>,
+    pub name: Name,
+    pub extends: Option<Extends>,
+    pub items: Vec<Item>,
+    pub span: Span,
+}
+
+/// Index of an attribute, `None` in [`Item`]s stands for no index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Index {
+    Value(Literal),
+    Others,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Alternative {
+    /// Empty for `when others`.
+    pub choices: Vec<Literal>,
+    pub items: Vec<Item>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ItemKind {
+    Attribute {
+        name: Name,
+        index: Option<Index>,
+        value: Expr,
+    },
+    Type {
+        name: Name,
+        values: Vec<Literal>,
+    },
+    Variable {
+        name: Name,
+        type_name: Option<Name>,
+        value: Expr,
+    },
+    Package {
+        name: Name,
+        extends: Option<Name>,
+        renames: Option<Name>,
+        items: Vec<Item>,
+    },
+    Case {
+        variable: Name,
+        alternatives: Vec<Alternative>,
+    },
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Item {
+    pub kind: ItemKind,
+    pub span: Span,
+}
+
+/// Concatenation of terms with `&`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Expr {
+    pub terms: Vec<Term>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Term {
+    String(Literal),
+    List(Vec<Expr>, Span),
+    Variable(Name),
+    Attribute {
+        prefix: Name,
+        name: Name,
+        index: Option<Index>,
+    },
+    /// Call of one of the built-in functions `External`,
+    /// `External_As_List` and `Split`.
+    Call {
+        function: Name,
+        arguments: Vec<Expr>,
+    },
+}
+
+impl Term {
+    pub fn span(&self) -> Span {
+        match self {
+            Term::String(literal) => literal.span,
+            Term::List(_, span) => *span,
+            Term::Variable(name) => name.span,
+            Term::Attribute { prefix, .. } => prefix.span,
+            Term::Call { function, .. } => function.span,
+        }
+    }
+}
@@ -0,0 +1,553 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use super::{
+    binding::{self, Attribute, AttributeValue, View},
+    error, evaluator, inspect, parser, paths, scenario, sources,
+};
+
+/// Environment variable selecting the backend, `libgpr2`, `gprinspect` or
+/// `native`.
+const BACKEND_VAR: &str = "GPR_RUST_BACKEND";
+
+/// Implementation used to load project trees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// libgpr2, linked into the build script.
+    #[cfg_attr(not(feature = "gprinspect"), default)]
+    Libgpr2,
+    /// `gprinspect --display=json` of the toolchain in `PATH`, i.e. the one
+    /// that also builds the project. Errors inside libgpr2 cannot abort the
+    /// build script.
+    #[cfg_attr(feature = "gprinspect", default)]
+    Gprinspect,
+    /// Parser and evaluator for GPR written in Rust, which does not need
+    /// any Ada tools. It supports variables, typed externals, case
+    /// statements, packages, `with` and `extends`, but does not configure a
+    /// toolchain.
+    Native,
+}
+
+impl Backend {
+    /// Backend selected by `GPR_RUST_BACKEND`, falling back to the default,
+    /// which is `Gprinspect` with the `gprinspect` feature and `Libgpr2`
+    /// otherwise.
+    pub fn from_env() -> Result<Backend, error::Error> {
+        match env::var(BACKEND_VAR) {
+            Ok(value) => match value.to_lowercase().as_str() {
+                "libgpr2" => Ok(Backend::Libgpr2),
+                "gprinspect" => Ok(Backend::Gprinspect),
+                "native" => Ok(Backend::Native),
+                _ => Err(error::Error::InvalidBackend { value }),
+            },
+            Err(_) => Ok(Backend::default()),
+        }
+    }
+}
+
+/// Package, name and index of an attribute, see [`attribute_key`].
+pub(crate) type AttributeKey = (Option<String>, String, Option<String>);
+
+/// Attributes of a view, keyed by [`attribute_key`].
+pub(crate) type Attributes = HashMap<AttributeKey, AttributeValue>;
+
+/// Attributes whose index is case sensitive.
+const CASE_SENSITIVE_INDEXES: &[&str] = &["external"];
+
+/// Key of an attribute. Package and name are not case sensitive, neither is
+/// the index except for attributes like `External` that are indexed by
+/// environment variables.
+pub(crate) fn attribute_key(pkg: Option<&str>, name: &str, index: Option<&str>) -> AttributeKey {
+    let name = name.to_lowercase();
+    let index = if CASE_SENSITIVE_INDEXES.contains(&name.as_str()) {
+        index.map(String::from)
+    } else {
+        index.map(str::to_lowercase)
+    };
+    (pkg.map(str::to_lowercase), name, index)
+}
+
+/// Where the value of an attribute is declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Origin {
+    /// Project file declaring the attribute, an extended project for
+    /// inherited attributes.
+    pub project: PathBuf,
+    /// Directory of the project each element of the value comes from,
+    /// which relative paths in the element refer to. Values such as
+    /// `Shared'Source_Dirs` come from another project than the one
+    /// declaring the attribute.
+    pub dirs: Vec<PathBuf>,
+}
+
+/// Origins of the attributes of a view, keyed by [`attribute_key`].
+pub(crate) type Origins = HashMap<AttributeKey, Origin>;
+
+/// Project extended by the project file `path` according to its `extends`
+/// clause and whether it is extended with `extends all`. The extended
+/// project is looked up relative to `path` first, then in `search_paths`.
+pub(crate) fn extension(
+    path: &Path,
+    search_paths: &[PathBuf],
+) -> Result<Option<(PathBuf, bool)>, error::Error> {
+    let ast = parser::parse(&fs::read_to_string(path)?, path)?;
+    let Some(extends) = ast.extends else {
+        return Ok(None);
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    std::iter::once(dir)
+        .chain(search_paths.iter().map(PathBuf::as_path))
+        .find_map(|dir| scenario::resolve_project_path(dir, &extends.path.value))
+        .map(|extended| Some((extended, extends.all)))
+        .ok_or_else(|| {
+            error::Error::ProjectNotFound(error::Exception::new(
+                2,
+                "ProjectNotFound",
+                &format!(
+                    "{}:{}:{}: extended project file \"{}\" not found",
+                    path.display(),
+                    extends.path.span.line,
+                    extends.path.span.column,
+                    extends.path.value
+                ),
+            ))
+        })
+}
+
+/// Value of attributes libgpr2 provides a default for, if they are not
+/// declared in a project.
+pub(crate) fn default_value(project: &str, name: &str) -> Option<AttributeValue> {
+    match name {
+        "name" => Some(AttributeValue::Single(String::from(project))),
+        "object_dir" | "exec_dir" => Some(AttributeValue::Single(String::from("."))),
+        "source_dirs" => Some(AttributeValue::List(vec![String::from(".")])),
+        "library_kind" => Some(AttributeValue::Single(String::from("static"))),
+        "library_standalone" => Some(AttributeValue::Single(String::from("no"))),
+        "library_auto_init" => Some(AttributeValue::Single(String::from("true"))),
+        "library_interface" | "interfaces" | "library_options" => {
+            Some(AttributeValue::List(Vec::new()))
+        }
+        _ => None,
+    }
+}
+
+/// Project tree that is evaluated completely when it is loaded, as done by
+/// the gprinspect and native backends. The views are identified by the
+/// paths of their project files.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub root_view: String,
+    pub config_view: Option<String>,
+    pub target: String,
+    pub src_subdirs: Option<String>,
+    pub views: Vec<String>,
+    pub view_data: HashMap<String, (View, Attributes)>,
+    /// Origins of the attributes of each view, recorded by the native
+    /// backend only.
+    pub origins: HashMap<String, Origins>,
+    pub context: HashMap<String, String>,
+}
+
+impl Snapshot {
+    /// Adds a view, configuration projects become the configuration view.
+    pub fn add_view(&mut self, view: View, attributes: Attributes) {
+        if view.kind == "k_configuration" {
+            self.config_view = Some(view.id.clone());
+        } else {
+            self.views.push(view.id.clone());
+        }
+        self.view_data.insert(view.id.clone(), (view, attributes));
+    }
+
+    fn data(&self, view: &str) -> Result<&(View, Attributes), error::Error> {
+        self.view_data.get(view).ok_or_else(|| {
+            error::Error::ViewNotFound(error::Exception::new(
+                2,
+                "UnknownView",
+                &format!("{view} is not part of the project tree"),
+            ))
+        })
+    }
+
+    pub fn view(&self, view: &str) -> Result<View, error::Error> {
+        Ok(self.data(view)?.0.clone())
+    }
+
+    pub fn get_view_attribute(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<Attribute, error::Error> {
+        let (data, attributes) = self.data(view)?;
+        let key = attribute_key(pkg, name, index);
+        let name = &key.1;
+        match attributes.get(&key) {
+            Some(value) => Ok(Attribute::new(value.clone(), false)),
+            None => {
+                match default_value(&data.name, name).filter(|_| pkg.is_none() && index.is_none()) {
+                    Some(value) => Ok(Attribute::new(value, true)),
+                    None => Err(error::Error::AttributeNotFound(error::Exception::new(
+                        2,
+                        "UndefinedAttribute",
+                        &format!("attribute {name} is not defined in {view}"),
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Where the attribute is declared, `None` if it has its default value.
+    pub fn origin(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<Option<&Origin>, error::Error> {
+        self.data(view)?;
+        Ok(self
+            .origins
+            .get(view)
+            .and_then(|origins| origins.get(&attribute_key(pkg, name, index))))
+    }
+
+    /// Indexes of all values of an indexed attribute.
+    pub fn attribute_indexes(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+    ) -> Result<Vec<String>, error::Error> {
+        let (pkg, name, _) = attribute_key(pkg, name, None);
+        let mut indexes: Vec<String> = self
+            .data(view)?
+            .1
+            .keys()
+            .filter(|(p, n, _)| *p == pkg && *n == name)
+            .filter_map(|(_, _, index)| index.clone())
+            .collect();
+        indexes.sort();
+        Ok(indexes)
+    }
+}
+
+/// The project tree of a libgpr2 or gprinspect tree evaluated by the native
+/// backend. It provides the information these backends do not expose: the
+/// indexes an attribute is declared for, where it is declared and, for
+/// gprinspect, the directories path values are relative to. The tree is only
+/// evaluated when such information is requested, which fails for projects the
+/// native backend cannot evaluate.
+#[derive(Debug)]
+pub struct Native {
+    file: PathBuf,
+    context: BTreeMap<String, String>,
+    config: Option<PathBuf>,
+    snapshot: OnceLock<Box<Snapshot>>,
+}
+
+impl Native {
+    fn new(file: &Path, context: &BTreeMap<String, String>, config: Option<&Path>) -> Native {
+        Native {
+            file: file.to_path_buf(),
+            context: context.clone(),
+            config: config.map(Path::to_path_buf),
+            snapshot: OnceLock::new(),
+        }
+    }
+
+    fn snapshot(&self) -> Result<&Snapshot, error::Error> {
+        if let Some(snapshot) = self.snapshot.get() {
+            return Ok(snapshot);
+        }
+        let snapshot = evaluator::load(&self.file, &self.context, self.config.as_deref(), None)?;
+        Ok(self.snapshot.get_or_init(|| Box::new(snapshot)))
+    }
+
+    /// Returns the snapshot and the id of the view of the project file
+    /// `path` in it.
+    fn view(&self, path: &Path) -> Result<(&Snapshot, String), error::Error> {
+        let snapshot = self.snapshot()?;
+        let id = path
+            .canonicalize()
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string();
+        Ok((snapshot, id))
+    }
+}
+
+/// Project tree loaded by one of the backends.
+#[derive(Debug)]
+pub enum Tree {
+    Libgpr2(binding::Tree, Native),
+    Gprinspect(Snapshot, Native),
+    Native(Snapshot),
+}
+
+impl Tree {
+    pub fn load(
+        backend: Backend,
+        file: &Path,
+        context: &BTreeMap<String, String>,
+        config: Option<&Path>,
+        src_subdirs: Option<&str>,
+    ) -> Result<Tree, error::Error> {
+        Ok(match backend {
+            Backend::Libgpr2 => Tree::Libgpr2(
+                binding::Tree::load(file, context, config, src_subdirs)?,
+                Native::new(file, context, config),
+            ),
+            Backend::Gprinspect => Tree::Gprinspect(
+                inspect::load(file, context, config, src_subdirs)?,
+                Native::new(file, context, config),
+            ),
+            Backend::Native => Tree::Native(evaluator::load(file, context, config, src_subdirs)?),
+        })
+    }
+
+    pub fn backend(&self) -> Backend {
+        match self {
+            Tree::Libgpr2(..) => Backend::Libgpr2,
+            Tree::Gprinspect(..) => Backend::Gprinspect,
+            Tree::Native(_) => Backend::Native,
+        }
+    }
+
+    pub fn context(&self) -> &HashMap<String, String> {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.context(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => &tree.context,
+        }
+    }
+
+    pub fn src_subdirs(&self) -> Option<&str> {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.src_subdirs(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => tree.src_subdirs.as_deref(),
+        }
+    }
+
+    pub fn root_view(&self) -> &str {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.root_view(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => &tree.root_view,
+        }
+    }
+
+    pub fn views(&self) -> &[String] {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.views(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => &tree.views,
+        }
+    }
+
+    pub fn view(&self, view: &str) -> Result<View, error::Error> {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.view(view),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => tree.view(view),
+        }
+    }
+
+    /// Project extended by `view` and whether it is extended with
+    /// `extends all`. The C API of libgpr2 does not report extensions, they
+    /// are read from the `extends` clause of the project file and looked up
+    /// like libgpr2 does.
+    pub fn extension(&self, view: &View) -> Result<Option<(PathBuf, bool)>, error::Error> {
+        match self {
+            Tree::Libgpr2(tree, _) => extension(&view.path, tree.search_paths()),
+            Tree::Gprinspect(..) | Tree::Native(_) => Ok(view
+                .extended
+                .clone()
+                .map(|extended| (extended, view.extends_all))),
+        }
+    }
+
+    pub fn config_view(&self) -> Option<&str> {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.config_view(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => tree.config_view.as_deref(),
+        }
+    }
+
+    pub fn target(&self) -> &str {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.target(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => &tree.target,
+        }
+    }
+
+    pub fn canonical_target(&self) -> &str {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.canonical_target(),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => &tree.target,
+        }
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Result<Attribute, error::Error> {
+        self.get_view_attribute(self.root_view(), None, name, None)
+    }
+
+    pub fn get_view_attribute(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<Attribute, error::Error> {
+        match self {
+            Tree::Libgpr2(tree, _) => tree.get_view_attribute(view, pkg, name, index),
+            Tree::Gprinspect(tree, _) | Tree::Native(tree) => {
+                tree.get_view_attribute(view, pkg, name, index)
+            }
+        }
+    }
+
+    /// The tree evaluated by the native backend and the id of `view` in it,
+    /// to look up `what` which the backend of the tree does not report.
+    fn native(&self, view: &str, what: &str) -> Result<(&Snapshot, String), error::Error> {
+        let (native, backend) = match self {
+            Tree::Libgpr2(_, native) => (native, "libgpr2"),
+            Tree::Gprinspect(_, native) => (native, "gprinspect"),
+            Tree::Native(tree) => return Ok((tree, String::from(view))),
+        };
+        native
+            .view(&self.view(view)?.path)
+            .map_err(|e| error::Error::not_reported(backend, what, e))
+    }
+
+    /// Indexes of all values of an indexed attribute of `view`. libgpr2 does
+    /// not report them, so they are taken from the native evaluation of the
+    /// project.
+    pub fn attribute_indexes(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+    ) -> Result<Vec<String>, error::Error> {
+        match self {
+            Tree::Gprinspect(tree, _) => tree.attribute_indexes(view, pkg, name),
+            Tree::Libgpr2(..) | Tree::Native(_) => {
+                let (snapshot, id) = self.native(view, "attribute indexes")?;
+                snapshot.attribute_indexes(&id, pkg, name)
+            }
+        }
+    }
+
+    /// Where the attribute of `view` is declared, `None` if it has its
+    /// default value. Only the native backend records this, it is taken from
+    /// the native evaluation of the project for the other backends.
+    pub fn origin(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<Option<Origin>, error::Error> {
+        let (snapshot, id) = self.native(view, "declaring projects")?;
+        Ok(snapshot.origin(&id, pkg, name, index)?.cloned())
+    }
+
+    /// Values of a path attribute of `view` as absolute paths, with
+    /// recursive directories (`dir/**`) expanded. libgpr2 reports the
+    /// directories of a view resolved by gpr2, other path attributes like
+    /// `Project_Files` are declared in the view itself and resolved against
+    /// its directory. The other backends resolve each value against the
+    /// directory of the project it comes from, default values against the
+    /// directory of `view`.
+    pub fn path_values(&self, view: &str, name: &str) -> Result<Vec<PathBuf>, error::Error> {
+        let data = self.view(view)?;
+        if let Some(dirs) = data.dirs.get(name) {
+            return Ok(dirs);
+        }
+        let values = match self.get_view_attribute(view, None, name, None)?.value {
+            AttributeValue::Single(value) => vec![value],
+            AttributeValue::List(values) => values,
+        };
+        let dirs = match self {
+            Tree::Libgpr2(..) => Vec::new(),
+            _ => self
+                .origin(view, None, name, None)?
+                .map(|origin| origin.dirs)
+                .unwrap_or_default(),
+        };
+        let dir = data.dir;
+        Ok(values
+            .iter()
+            .enumerate()
+            .flat_map(|(i, value)| paths::resolve(dirs.get(i).unwrap_or(&dir), value))
+            .collect())
+    }
+
+    /// Source files of `view`. The native and gprinspect backends select
+    /// them from the source directories like gprbuild does.
+    pub fn sources(&self, view: &str) -> Result<Vec<PathBuf>, error::Error> {
+        if let Tree::Libgpr2(tree, _) = self {
+            return tree.sources(view);
+        }
+        let data = self.view(view)?;
+        if matches!(
+            data.kind.as_str(),
+            "k_abstract" | "k_aggregate" | "k_aggregate_library" | "k_configuration"
+        ) {
+            return Ok(Vec::new());
+        }
+        let attr = |pkg: Option<&str>, name: &str, index: Option<&str>| {
+            self.get_view_attribute(view, pkg, name, index)
+                .ok()
+                .map(|a| a.value)
+        };
+        let mut rules = sources::Rules::new(&data.dir, &attr)?;
+        for name in ["spec", "body", "specification", "implementation"] {
+            for unit in self.attribute_indexes(view, Some("naming"), name)? {
+                if let Some(AttributeValue::Single(file)) = attr(Some("naming"), name, Some(&unit))
+                {
+                    rules.name(&file);
+                }
+            }
+        }
+        let excluded = match attr(None, "excluded_source_dirs", None) {
+            Some(_) => self.path_values(view, "excluded_source_dirs")?,
+            None => Vec::new(),
+        };
+        let dirs: Vec<PathBuf> = self
+            .path_values(view, "source_dirs")?
+            .into_iter()
+            .filter(|dir| !excluded.contains(dir))
+            .collect();
+        Ok(sources::select(&dirs, &rules))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_reported() {
+        let snapshot =
+            inspect::parse(&fs::read_to_string("testdata/gprinspect/testlib.json").unwrap())
+                .unwrap();
+        let tree = Tree::Gprinspect(
+            snapshot,
+            Native::new(Path::new("/prj/testlib.gpr"), &BTreeMap::new(), None),
+        );
+        let view = "/prj/testlib.gpr";
+        assert_eq!(
+            tree.attribute_indexes(view, Some("compiler"), "default_switches")
+                .unwrap(),
+            vec!["ada"]
+        );
+        let error = tree.origin(view, None, "library_dir", None).unwrap_err();
+        assert!(
+            matches!(&error, error::Error::NotReported { what, .. } if what == "declaring projects"),
+            "{error}"
+        );
+        assert!(error.to_string().starts_with("gprinspect does not report"));
+    }
+}
@@ -0,0 +1,1342 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error,
+    lexer::{self, Token, TokenKind},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TypeRef {
+    Named(String),
+    Access { constant: bool, target: String },
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    In,
+    Out,
+    InOut,
+}
+
+#[derive(Debug)]
+struct Param {
+    name: String,
+    mode: Mode,
+    type_ref: TypeRef,
+}
+
+#[derive(Debug)]
+struct Subprogram {
+    name: String,
+    params: Vec<Param>,
+    result: Option<TypeRef>,
+    aspects: Vec<(String, Option<String>)>,
+    generic: bool,
+    line: usize,
+}
+
+#[derive(Debug)]
+enum TypeDef {
+    Record(Vec<(String, TypeRef)>),
+    Enum(Vec<String>),
+    Access { constant: bool, target: String },
+    Alias(String),
+    Opaque,
+    Unsupported(String),
+}
+
+#[derive(Debug)]
+struct TypeDecl {
+    name: String,
+    def: TypeDef,
+    aspects: Vec<(String, Option<String>)>,
+    line: usize,
+}
+
+/// Pragma with its arguments as `(name, value)` pairs, positional arguments
+/// have no name.
+#[derive(Debug)]
+struct Pragma {
+    name: String,
+    args: Vec<(Option<String>, String)>,
+    line: usize,
+}
+
+/// Declarations of an Ada package spec relevant for C bindings.
+#[derive(Debug, Default)]
+struct Spec {
+    unit: String,
+    uses: Vec<String>,
+    types: Vec<TypeDecl>,
+    subprograms: Vec<Subprogram>,
+    pragmas: Vec<Pragma>,
+    representations: HashMap<String, Vec<(String, String)>>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    file: &'a Path,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, file: &'a Path) -> Parser<'a> {
+        Parser {
+            tokens: lexer::tokenize_ada(source)
+                .into_iter()
+                .filter(|t| !t.is_trivia())
+                .collect(),
+            pos: 0,
+            file,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + offset)
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|t| t.kind == kind)
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_keyword(keyword))
+    }
+
+    fn line(&self) -> usize {
+        self.peek()
+            .or(self.tokens.last())
+            .map(|t| t.line)
+            .unwrap_or(1)
+    }
+
+    fn error(&self, message: &str) -> error::Error {
+        error::Error::binding(self.file, self.line(), message)
+    }
+
+    fn bump(&mut self) -> Result<Token<'a>, error::Error> {
+        let token = *self
+            .peek()
+            .ok_or_else(|| self.error("unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn accept(&mut self, kind: TokenKind) -> bool {
+        let found = self.at(kind);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), error::Error> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected \"{keyword}\"")))
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Token<'a>, error::Error> {
+        if self.at(kind) {
+            self.bump()
+        } else {
+            Err(self.error(&format!("expected {kind:?}")))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, error::Error> {
+        Ok(String::from(self.expect(TokenKind::Identifier)?.text))
+    }
+
+    fn dotted_name(&mut self) -> Result<String, error::Error> {
+        let mut name = self.identifier()?;
+        while self.at(TokenKind::Dot)
+            && self
+                .peek_at(1)
+                .is_some_and(|t| t.kind == TokenKind::Identifier)
+        {
+            self.pos += 1;
+            name.push('.');
+            name.push_str(&self.identifier()?);
+        }
+        Ok(name)
+    }
+
+    /// Skips tokens up to and including the next semicolon outside of
+    /// parentheses.
+    fn skip_to_semicolon(&mut self) -> Result<(), error::Error> {
+        let mut depth = 0usize;
+        loop {
+            let token = self.bump()?;
+            match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth = depth.saturating_sub(1),
+                TokenKind::Semicolon if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Collects the text of an expression up to the next `,`, `;`, `)` or
+    /// `is` outside of parentheses.
+    fn expression(&mut self) -> Result<String, error::Error> {
+        let mut depth = 0usize;
+        let mut text = Vec::new();
+        while let Some(token) = self.peek() {
+            match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen if depth == 0 => break,
+                TokenKind::RightParen => depth -= 1,
+                TokenKind::Comma | TokenKind::Semicolon if depth == 0 => break,
+                TokenKind::Identifier if depth == 0 && token.is_keyword("is") => break,
+                _ => {}
+            }
+            text.push(
+                token
+                    .string_value()
+                    .unwrap_or_else(|| String::from(token.text)),
+            );
+            self.pos += 1;
+        }
+        Ok(text.join(" "))
+    }
+
+    fn aspects(&mut self) -> Result<Vec<(String, Option<String>)>, error::Error> {
+        let mut aspects = Vec::new();
+        loop {
+            let name = self.dotted_name()?.to_lowercase();
+            if self.accept(TokenKind::Apostrophe) {
+                self.identifier()?;
+            }
+            let value = if self.accept(TokenKind::Arrow) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            aspects.push((name, value));
+            if !self.accept(TokenKind::Comma) {
+                return Ok(aspects);
+            }
+        }
+    }
+
+    /// Parses aspects if present and the terminating semicolon.
+    fn end_of_declaration(&mut self) -> Result<Vec<(String, Option<String>)>, error::Error> {
+        let mut aspects = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            if depth == 0 && self.accept_keyword("with") {
+                aspects.extend(self.aspects()?);
+                continue;
+            }
+            let token = self.bump()?;
+            match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth = depth.saturating_sub(1),
+                TokenKind::Semicolon if depth == 0 => return Ok(aspects),
+                _ => {}
+            }
+        }
+    }
+
+    fn type_ref(&mut self) -> Result<TypeRef, error::Error> {
+        if self.accept_keyword("not") {
+            self.expect_keyword("null")?;
+        }
+        let type_ref = if self.accept_keyword("access") {
+            let constant = self.accept_keyword("constant");
+            self.accept_keyword("all");
+            if self.at_keyword("procedure") || self.at_keyword("function") {
+                return Ok(TypeRef::Unsupported(String::from(
+                    "access to subprogram types cannot be mapped",
+                )));
+            }
+            TypeRef::Access {
+                constant,
+                target: self.dotted_name()?,
+            }
+        } else {
+            TypeRef::Named(self.dotted_name()?)
+        };
+        if self.at(TokenKind::Apostrophe) {
+            return Ok(TypeRef::Unsupported(String::from(
+                "attribute references cannot be mapped",
+            )));
+        }
+        if self.at(TokenKind::LeftParen) || self.at_keyword("range") {
+            return Ok(TypeRef::Unsupported(String::from(
+                "constrained subtypes cannot be mapped",
+            )));
+        }
+        Ok(type_ref)
+    }
+
+    fn params(&mut self) -> Result<Vec<Param>, error::Error> {
+        let mut params = Vec::new();
+        if !self.accept(TokenKind::LeftParen) {
+            return Ok(params);
+        }
+        loop {
+            let mut names = vec![self.identifier()?];
+            while self.accept(TokenKind::Comma) {
+                names.push(self.identifier()?);
+            }
+            self.expect(TokenKind::Colon)?;
+            self.accept_keyword("aliased");
+            let input = self.accept_keyword("in");
+            let output = self.accept_keyword("out");
+            let mode = match (input, output) {
+                (_, false) => Mode::In,
+                (false, true) => Mode::Out,
+                (true, true) => Mode::InOut,
+            };
+            let type_ref = self.type_ref()?;
+            let mut depth = 0usize;
+            while !(depth == 0 && (self.at(TokenKind::Semicolon) || self.at(TokenKind::RightParen)))
+            {
+                match self.bump()?.kind {
+                    TokenKind::LeftParen => depth += 1,
+                    TokenKind::RightParen => depth -= 1,
+                    _ => {}
+                }
+            }
+            for name in names {
+                params.push(Param {
+                    name,
+                    mode,
+                    type_ref: type_ref.clone(),
+                });
+            }
+            if self.accept(TokenKind::RightParen) {
+                return Ok(params);
+            }
+            self.expect(TokenKind::Semicolon)?;
+        }
+    }
+
+    fn subprogram(&mut self, generic: bool) -> Result<Subprogram, error::Error> {
+        let line = self.line();
+        let function = self.bump()?.is_keyword("function");
+        let name = if self.at(TokenKind::String) {
+            self.bump()?.string_value().unwrap_or_default()
+        } else {
+            self.dotted_name()?
+        };
+        let params = self.params()?;
+        let result = if function {
+            self.expect_keyword("return")?;
+            Some(self.type_ref()?)
+        } else {
+            None
+        };
+        Ok(Subprogram {
+            name,
+            params,
+            result,
+            aspects: self.end_of_declaration()?,
+            generic,
+            line,
+        })
+    }
+
+    fn record(&mut self) -> Result<TypeDef, error::Error> {
+        let mut components = Vec::new();
+        while !self.at_keyword("end") {
+            if self.accept_keyword("null") {
+                self.expect(TokenKind::Semicolon)?;
+                continue;
+            }
+            if self.at_keyword("case") {
+                return Ok(TypeDef::Unsupported(String::from(
+                    "variant records cannot be mapped",
+                )));
+            }
+            let mut names = vec![self.identifier()?];
+            while self.accept(TokenKind::Comma) {
+                names.push(self.identifier()?);
+            }
+            self.expect(TokenKind::Colon)?;
+            self.accept_keyword("aliased");
+            let type_ref = self.type_ref()?;
+            self.skip_to_semicolon()?;
+            components.extend(names.into_iter().map(|n| (n, type_ref.clone())));
+        }
+        self.expect_keyword("end")?;
+        self.expect_keyword("record")?;
+        Ok(TypeDef::Record(components))
+    }
+
+    fn type_decl(&mut self) -> Result<Option<TypeDecl>, error::Error> {
+        let line = self.line();
+        self.expect_keyword("type")?;
+        let name = self.identifier()?;
+        let discriminated = self.at(TokenKind::LeftParen);
+        if discriminated {
+            self.expression()?;
+            self.expect(TokenKind::RightParen)?;
+        }
+        if self.accept(TokenKind::Semicolon) {
+            return Ok(None);
+        }
+        self.expect_keyword("is")?;
+        let mut tagged = false;
+        while self.at_keyword("abstract")
+            || self.at_keyword("tagged")
+            || self.at_keyword("limited")
+            || self.at_keyword("synchronized")
+        {
+            tagged |= self.bump()?.is_keyword("tagged");
+        }
+        let def = if self.accept_keyword("record") {
+            self.record()?
+        } else if self.accept_keyword("null") {
+            self.expect_keyword("record")?;
+            TypeDef::Record(Vec::new())
+        } else if self.accept_keyword("private") {
+            TypeDef::Opaque
+        } else if self.accept(TokenKind::LeftParen) {
+            let mut literals = vec![self.identifier()?];
+            while self.accept(TokenKind::Comma) {
+                literals.push(self.identifier()?);
+            }
+            self.expect(TokenKind::RightParen)?;
+            TypeDef::Enum(literals)
+        } else if self.at_keyword("access") || self.at_keyword("not") {
+            match self.type_ref()? {
+                TypeRef::Access { constant, target } => TypeDef::Access { constant, target },
+                TypeRef::Unsupported(reason) => TypeDef::Unsupported(reason),
+                TypeRef::Named(_) => TypeDef::Unsupported(String::from("unexpected type")),
+            }
+        } else if self.accept_keyword("new") {
+            match self.type_ref()? {
+                TypeRef::Named(parent) if !self.at_keyword("with") => TypeDef::Alias(parent),
+                TypeRef::Unsupported(reason) => TypeDef::Unsupported(reason),
+                _ => TypeDef::Unsupported(String::from("type extensions cannot be mapped")),
+            }
+        } else {
+            let kind = self
+                .peek()
+                .map(|t| t.text.to_lowercase())
+                .unwrap_or_default();
+            TypeDef::Unsupported(format!(
+                "{kind} types cannot be mapped, use a type from Interfaces.C"
+            ))
+        };
+        let def = if tagged {
+            TypeDef::Unsupported(String::from("tagged types cannot be mapped"))
+        } else if discriminated {
+            TypeDef::Unsupported(String::from("discriminated types cannot be mapped"))
+        } else {
+            def
+        };
+        Ok(Some(TypeDecl {
+            name,
+            def,
+            aspects: self.end_of_declaration()?,
+            line,
+        }))
+    }
+
+    fn subtype_decl(&mut self) -> Result<TypeDecl, error::Error> {
+        let line = self.line();
+        self.expect_keyword("subtype")?;
+        let name = self.identifier()?;
+        self.expect_keyword("is")?;
+        let def = match self.type_ref()? {
+            TypeRef::Named(parent) => TypeDef::Alias(parent),
+            TypeRef::Access { constant, target } => TypeDef::Access { constant, target },
+            TypeRef::Unsupported(reason) => TypeDef::Unsupported(reason),
+        };
+        Ok(TypeDecl {
+            name,
+            def,
+            aspects: self.end_of_declaration()?,
+            line,
+        })
+    }
+
+    fn pragma(&mut self) -> Result<Pragma, error::Error> {
+        let line = self.line();
+        self.expect_keyword("pragma")?;
+        let name = self.identifier()?.to_lowercase();
+        let mut args = Vec::new();
+        if self.accept(TokenKind::LeftParen) {
+            loop {
+                let arg_name = if self.at(TokenKind::Identifier)
+                    && self.peek_at(1).is_some_and(|t| t.kind == TokenKind::Arrow)
+                {
+                    let arg_name = self.identifier()?.to_lowercase();
+                    self.pos += 1;
+                    Some(arg_name)
+                } else {
+                    None
+                };
+                args.push((arg_name, self.expression()?));
+                if !self.accept(TokenKind::Comma) {
+                    break;
+                }
+            }
+            self.expect(TokenKind::RightParen)?;
+        }
+        self.skip_to_semicolon()?;
+        Ok(Pragma { name, args, line })
+    }
+
+    fn representation(&mut self, spec: &mut Spec) -> Result<(), error::Error> {
+        self.expect_keyword("for")?;
+        let name = self.dotted_name()?;
+        if self.accept_keyword("use") && self.accept(TokenKind::LeftParen) {
+            let mut values = Vec::new();
+            loop {
+                let literal = self.identifier()?;
+                self.expect(TokenKind::Arrow)?;
+                values.push((literal.to_lowercase(), self.expression()?));
+                if !self.accept(TokenKind::Comma) {
+                    break;
+                }
+            }
+            spec.representations.insert(name.to_lowercase(), values);
+        }
+        self.skip_to_semicolon()
+    }
+
+    /// Skips a task or protected declaration including its body.
+    fn skip_block(&mut self) -> Result<(), error::Error> {
+        let mut depth = 0usize;
+        loop {
+            let token = self.bump()?;
+            if token.is_keyword("is") && depth == 0 {
+                while !self.at_keyword("end") {
+                    self.bump()?;
+                }
+                return self.skip_to_semicolon();
+            }
+            match token.kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth = depth.saturating_sub(1),
+                TokenKind::Semicolon if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn declarations(&mut self, spec: &mut Spec, prefix: &str) -> Result<(), error::Error> {
+        let mut generic = false;
+        loop {
+            if self.accept_keyword("end") {
+                return self.skip_to_semicolon();
+            }
+            if self.accept_keyword("private") || self.accept_keyword("overriding") {
+                continue;
+            }
+            if self.at_keyword("not") && self.peek_at(1).is_some_and(|t| t.is_keyword("overriding"))
+            {
+                self.pos += 2;
+                continue;
+            }
+            if self.accept_keyword("generic") {
+                generic = true;
+                while !(self.at_keyword("function")
+                    || self.at_keyword("procedure")
+                    || self.at_keyword("package"))
+                {
+                    self.skip_to_semicolon()?;
+                }
+                continue;
+            }
+            if self.at_keyword("type") {
+                if let Some(mut decl) = self.type_decl()? {
+                    decl.name = format!("{prefix}{}", decl.name);
+                    spec.types.push(decl);
+                }
+            } else if self.at_keyword("subtype") {
+                let mut decl = self.subtype_decl()?;
+                decl.name = format!("{prefix}{}", decl.name);
+                spec.types.push(decl);
+            } else if self.at_keyword("function") || self.at_keyword("procedure") {
+                spec.subprograms.push(self.subprogram(generic)?);
+            } else if self.at_keyword("pragma") {
+                spec.pragmas.push(self.pragma()?);
+            } else if self.at_keyword("for") {
+                self.representation(spec)?;
+            } else if self.at_keyword("use")
+                && !self.peek_at(1).is_some_and(|t| t.is_keyword("type"))
+            {
+                self.pos += 1;
+                spec.uses.push(self.dotted_name()?.to_lowercase());
+                while self.accept(TokenKind::Comma) {
+                    spec.uses.push(self.dotted_name()?.to_lowercase());
+                }
+                self.expect(TokenKind::Semicolon)?;
+            } else if self.at_keyword("task") || self.at_keyword("protected") {
+                self.skip_block()?;
+            } else if self.accept_keyword("package") {
+                let name = self.dotted_name()?;
+                if self.accept_keyword("with") {
+                    self.aspects()?;
+                }
+                if self.at_keyword("is") && !self.peek_at(1).is_some_and(|t| t.is_keyword("new")) {
+                    self.pos += 1;
+                    if generic {
+                        self.declarations(&mut Spec::default(), "")?;
+                    } else {
+                        self.declarations(spec, &format!("{prefix}{name}."))?;
+                    }
+                } else {
+                    self.skip_to_semicolon()?;
+                }
+            } else {
+                self.skip_to_semicolon()?;
+            }
+            generic = false;
+        }
+    }
+
+    fn spec(&mut self) -> Result<Spec, error::Error> {
+        let mut spec = Spec::default();
+        loop {
+            if self.accept_keyword("package") {
+                break;
+            }
+            if self.accept_keyword("use") {
+                spec.uses.push(self.dotted_name()?.to_lowercase());
+                while self.accept(TokenKind::Comma) {
+                    spec.uses.push(self.dotted_name()?.to_lowercase());
+                }
+                self.expect(TokenKind::Semicolon)?;
+            } else if self.at_keyword("generic") {
+                return Err(self.error("generic packages cannot be mapped"));
+            } else {
+                self.skip_to_semicolon()?;
+            }
+        }
+        spec.unit = self.dotted_name()?;
+        if self.accept_keyword("with") {
+            self.aspects()?;
+        }
+        self.expect_keyword("is")?;
+        self.declarations(&mut spec, "")?;
+        Ok(spec)
+    }
+}
+
+/// C compatible types of the Ada standard library and their Rust equivalent.
+const PREDEFINED: &[(&str, &str)] = &[
+    ("interfaces.c.int", "core::ffi::c_int"),
+    ("interfaces.c.unsigned", "core::ffi::c_uint"),
+    ("interfaces.c.short", "core::ffi::c_short"),
+    ("interfaces.c.unsigned_short", "core::ffi::c_ushort"),
+    ("interfaces.c.long", "core::ffi::c_long"),
+    ("interfaces.c.unsigned_long", "core::ffi::c_ulong"),
+    ("interfaces.c.long_long", "core::ffi::c_longlong"),
+    ("interfaces.c.unsigned_long_long", "core::ffi::c_ulonglong"),
+    ("interfaces.c.char", "core::ffi::c_char"),
+    ("interfaces.c.plain_char", "core::ffi::c_char"),
+    ("interfaces.c.signed_char", "core::ffi::c_schar"),
+    ("interfaces.c.unsigned_char", "core::ffi::c_uchar"),
+    ("interfaces.c.c_float", "core::ffi::c_float"),
+    ("interfaces.c.double", "core::ffi::c_double"),
+    ("interfaces.c.size_t", "usize"),
+    ("interfaces.c.ptrdiff_t", "isize"),
+    ("interfaces.c.c_bool", "bool"),
+    ("interfaces.c.strings.chars_ptr", "*mut core::ffi::c_char"),
+    ("system.address", "*mut core::ffi::c_void"),
+    ("interfaces.integer_8", "i8"),
+    ("interfaces.integer_16", "i16"),
+    ("interfaces.integer_32", "i32"),
+    ("interfaces.integer_64", "i64"),
+    ("interfaces.unsigned_8", "u8"),
+    ("interfaces.unsigned_16", "u16"),
+    ("interfaces.unsigned_32", "u32"),
+    ("interfaces.unsigned_64", "u64"),
+    ("interfaces.ieee_float_32", "f32"),
+    ("interfaces.ieee_float_64", "f64"),
+    ("boolean", "bool"),
+    ("standard.boolean", "bool"),
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Converts an Ada name into a Rust value identifier.
+pub(crate) fn value_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.as_str() {
+        "self" | "super" | "crate" => format!("{name}_"),
+        n if RUST_KEYWORDS.contains(&n) => format!("r#{n}"),
+        _ => name,
+    }
+}
+
+/// Converts an Ada name such as `Point_Type` or `Shapes.Point` into a Rust
+/// type name (`PointType`, `ShapesPoint`).
+pub(crate) fn type_name(name: &str) -> String {
+    name.split(['_', '.'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => {
+                    first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+                }
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn aspect<'s>(aspects: &'s [(String, Option<String>)], name: &str) -> Option<Option<&'s str>> {
+    aspects
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_deref())
+}
+
+fn is_true(value: Option<&str>) -> bool {
+    value.is_none_or(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// Subprogram exported with C convention together with its link name.
+struct Export<'s> {
+    subprogram: &'s Subprogram,
+    link_name: String,
+}
+
+struct Generator<'s> {
+    spec: &'s Spec,
+    file: &'s Path,
+    /// Indices of the local types required by the exported subprograms.
+    needed: Vec<usize>,
+}
+
+impl<'s> Generator<'s> {
+    fn find_type(&self, name: &str) -> Option<usize> {
+        let lower = name.to_lowercase();
+        let unit = format!("{}.", self.spec.unit.to_lowercase());
+        let local = lower.strip_prefix(&unit).unwrap_or(&lower);
+        self.spec
+            .types
+            .iter()
+            .position(|t| t.name.to_lowercase() == local)
+    }
+
+    fn predefined(&self, name: &str) -> Option<&'static str> {
+        let lower = name.to_lowercase();
+        std::iter::once(lower.clone())
+            .chain(self.spec.uses.iter().map(|u| format!("{u}.{lower}")))
+            .find_map(|candidate| {
+                PREDEFINED
+                    .iter()
+                    .find(|(ada, _)| *ada == candidate)
+                    .map(|(_, rust)| *rust)
+            })
+    }
+
+    fn conventions(&self, name: &str) -> Option<String> {
+        let decl = &self.spec.types[self.find_type(name)?];
+        if let Some(Some(convention)) = aspect(&decl.aspects, "convention") {
+            return Some(convention.to_lowercase());
+        }
+        self.spec
+            .pragmas
+            .iter()
+            .filter(|p| p.name == "convention" && p.args.len() == 2)
+            .find(|p| p.args[1].1.eq_ignore_ascii_case(&decl.name))
+            .map(|p| p.args[0].1.to_lowercase())
+    }
+
+    /// Maps a named type to Rust. The returned flag is set for record types
+    /// that C expects to be passed by reference.
+    fn named(&mut self, name: &str, line: usize) -> Result<(String, bool), error::Error> {
+        if let Some(rust) = self.predefined(name) {
+            return Ok((String::from(rust), false));
+        }
+        let index = self.find_type(name).ok_or_else(|| {
+            error::Error::binding(
+                self.file,
+                line,
+                &format!("type {name} cannot be mapped, use a type from Interfaces.C"),
+            )
+        })?;
+        let decl = &self.spec.types[index];
+        let by_reference = match &decl.def {
+            TypeDef::Unsupported(reason) => {
+                return Err(error::Error::binding(
+                    self.file,
+                    decl.line,
+                    &format!("type {}: {reason}", decl.name),
+                ))
+            }
+            TypeDef::Record(_) | TypeDef::Enum(_) => {
+                let convention = self.conventions(name);
+                if !matches!(convention.as_deref(), Some("c") | Some("c_pass_by_copy")) {
+                    return Err(error::Error::binding(
+                        self.file,
+                        decl.line,
+                        &format!("type {} does not have convention C", decl.name),
+                    ));
+                }
+                matches!(decl.def, TypeDef::Record(_)) && convention.as_deref() == Some("c")
+            }
+            TypeDef::Opaque => true,
+            TypeDef::Access { .. } | TypeDef::Alias(_) => false,
+        };
+        if !self.needed.contains(&index) {
+            self.needed.push(index);
+            self.dependencies(index)?;
+        }
+        Ok((type_name(&decl.name), by_reference))
+    }
+
+    /// Checks whether `name` denotes a private type, possibly through
+    /// derived types. The size of such types is unknown to Rust.
+    fn is_opaque(&self, name: &str) -> bool {
+        let mut name = name;
+        for _ in 0..self.spec.types.len() {
+            let Some(index) = self.find_type(name) else {
+                return false;
+            };
+            match &self.spec.types[index].def {
+                TypeDef::Opaque => return true,
+                TypeDef::Alias(target) => name = target,
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    fn dependencies(&mut self, index: usize) -> Result<(), error::Error> {
+        let decl = &self.spec.types[index];
+        let line = decl.line;
+        match &decl.def {
+            TypeDef::Record(components) => {
+                for (component, type_ref) in components {
+                    if let TypeRef::Named(name) = type_ref {
+                        if self.is_opaque(name) {
+                            return Err(error::Error::binding(
+                                self.file,
+                                line,
+                                &format!(
+                                    "component {component} of {} has the private type {name}, \
+                                     which can only be mapped through an access type",
+                                    decl.name
+                                ),
+                            ));
+                        }
+                    }
+                    self.type_ref(type_ref, line)?;
+                }
+            }
+            TypeDef::Access { target, .. } | TypeDef::Alias(target) => {
+                self.named(target, line)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn type_ref(&mut self, type_ref: &TypeRef, line: usize) -> Result<String, error::Error> {
+        match type_ref {
+            TypeRef::Named(name) => Ok(self.named(name, line)?.0),
+            TypeRef::Access { constant, target } => {
+                let target = self.named(target, line)?.0;
+                Ok(format!(
+                    "*{} {target}",
+                    if *constant { "const" } else { "mut" }
+                ))
+            }
+            TypeRef::Unsupported(reason) => Err(error::Error::binding(self.file, line, reason)),
+        }
+    }
+
+    fn param(&mut self, param: &Param, line: usize) -> Result<String, error::Error> {
+        let rust = match &param.type_ref {
+            TypeRef::Named(name) => {
+                let (rust, by_reference) = self.named(name, line)?;
+                match param.mode {
+                    Mode::In if by_reference => format!("*const {rust}"),
+                    Mode::In => rust,
+                    Mode::Out | Mode::InOut => format!("*mut {rust}"),
+                }
+            }
+            type_ref => self.type_ref(type_ref, line)?,
+        };
+        Ok(format!("{}: {rust}", value_name(&param.name)))
+    }
+
+    fn exports(&self) -> Result<Vec<Export<'s>>, error::Error> {
+        let mut exports = Vec::new();
+        for subprogram in &self.spec.subprograms {
+            let mut export = aspect(&subprogram.aspects, "export").is_some_and(is_true);
+            let mut convention = aspect(&subprogram.aspects, "convention")
+                .flatten()
+                .map(str::to_lowercase);
+            let mut link_name = aspect(&subprogram.aspects, "external_name")
+                .or(aspect(&subprogram.aspects, "link_name"))
+                .flatten()
+                .map(String::from);
+            for pragma in self.spec.pragmas.iter().filter(|p| p.name == "export") {
+                let arg = |index: usize, name: &str| {
+                    pragma
+                        .args
+                        .iter()
+                        .find(|(n, _)| n.as_deref() == Some(name))
+                        .or(pragma.args.get(index).filter(|(n, _)| n.is_none()))
+                        .map(|(_, v)| v.clone())
+                };
+                if !arg(1, "entity").is_some_and(|e| e.eq_ignore_ascii_case(&subprogram.name)) {
+                    continue;
+                }
+                if self
+                    .spec
+                    .subprograms
+                    .iter()
+                    .filter(|s| s.name.eq_ignore_ascii_case(&subprogram.name))
+                    .count()
+                    > 1
+                {
+                    return Err(error::Error::binding(
+                        self.file,
+                        pragma.line,
+                        &format!(
+                            "pragma Export for overloaded subprogram {}",
+                            subprogram.name
+                        ),
+                    ));
+                }
+                export = true;
+                convention = arg(0, "convention").map(|c| c.to_lowercase());
+                link_name = arg(2, "external_name").or(arg(3, "link_name"));
+            }
+            if !export {
+                continue;
+            }
+            if convention.as_deref() != Some("c") {
+                return Err(error::Error::binding(
+                    self.file,
+                    subprogram.line,
+                    &format!(
+                        "exported subprogram {} does not have convention C",
+                        subprogram.name
+                    ),
+                ));
+            }
+            if subprogram.generic {
+                return Err(error::Error::binding(
+                    self.file,
+                    subprogram.line,
+                    &format!("generic subprogram {} cannot be mapped", subprogram.name),
+                ));
+            }
+            exports.push(Export {
+                subprogram,
+                link_name: link_name.unwrap_or_else(|| subprogram.name.to_lowercase()),
+            });
+        }
+        Ok(exports)
+    }
+
+    fn type_decl(&mut self, index: usize, code: &mut String) -> Result<(), error::Error> {
+        let decl = &self.spec.types[index];
+        let name = type_name(&decl.name);
+        let line = decl.line;
+        let _ = writeln!(code, "/// {}.{}", self.spec.unit, decl.name);
+        match &decl.def {
+            TypeDef::Record(components) => {
+                let _ = writeln!(
+                    code,
+                    "#[repr(C)]\n#[derive(Debug, Clone, Copy)]\npub struct {name} {{"
+                );
+                for (component, type_ref) in components {
+                    let rust = self.type_ref(type_ref, line)?;
+                    let _ = writeln!(code, "    pub {}: {rust},", value_name(component));
+                }
+                code.push_str("}\n");
+            }
+            TypeDef::Enum(literals) => {
+                let representation = self.spec.representations.get(&decl.name.to_lowercase());
+                let _ = writeln!(
+                    code,
+                    "#[repr(C)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum {name} {{"
+                );
+                for literal in literals {
+                    let value = representation.and_then(|r| {
+                        r.iter()
+                            .find(|(l, _)| l.eq_ignore_ascii_case(literal))
+                            .map(|(_, v)| v.replace(' ', ""))
+                    });
+                    match value {
+                        Some(v) if v.parse::<i64>().is_ok() => {
+                            let _ = writeln!(code, "    {} = {v},", type_name(literal));
+                        }
+                        Some(v) => {
+                            return Err(error::Error::binding(
+                                self.file,
+                                line,
+                                &format!("representation {v} of {literal} cannot be mapped"),
+                            ))
+                        }
+                        None => {
+                            let _ = writeln!(code, "    {},", type_name(literal));
+                        }
+                    }
+                }
+                code.push_str("}\n");
+            }
+            TypeDef::Access { constant, target } => {
+                let target = self.named(target, line)?.0;
+                let mutability = if *constant { "const" } else { "mut" };
+                let _ = writeln!(code, "pub type {name} = *{mutability} {target};");
+            }
+            TypeDef::Alias(target) => {
+                let target = self.named(target, line)?.0;
+                let _ = writeln!(code, "pub type {name} = {target};");
+            }
+            TypeDef::Opaque => {
+                let _ = writeln!(
+                    code,
+                    "#[repr(C)]\npub struct {name} {{\n    _private: [u8; 0],\n}}"
+                );
+            }
+            TypeDef::Unsupported(reason) => {
+                return Err(error::Error::binding(self.file, line, reason))
+            }
+        }
+        code.push('\n');
+        Ok(())
+    }
+
+    fn generate(&mut self, code: &mut String) -> Result<(), error::Error> {
+        let exports = self.exports()?;
+        let mut functions = String::new();
+        for export in &exports {
+            let subprogram = export.subprogram;
+            let params = subprogram
+                .params
+                .iter()
+                .map(|p| self.param(p, subprogram.line))
+                .collect::<Result<Vec<_>, _>>()?;
+            let result = match &subprogram.result {
+                Some(type_ref) => format!(" -> {}", self.type_ref(type_ref, subprogram.line)?),
+                None => String::new(),
+            };
+            let _ = writeln!(functions, "    /// {}.{}", self.spec.unit, subprogram.name);
+            let name = value_name(&export.link_name);
+            if name != export.link_name {
+                let _ = writeln!(functions, "    #[link_name = \"{}\"]", export.link_name);
+            }
+            let _ = writeln!(
+                functions,
+                "    pub fn {name}({}){result};",
+                params.join(", ")
+            );
+        }
+        let mut needed = self.needed.clone();
+        needed.sort_unstable();
+        for index in needed {
+            self.type_decl(index, code)?;
+        }
+        if !exports.is_empty() {
+            let _ = writeln!(code, "unsafe extern \"C\" {{\n{functions}}}\n");
+        }
+        Ok(())
+    }
+}
+
+/// Casing of the file names of Ada sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Casing {
+    Lowercase,
+    Uppercase,
+    Mixedcase,
+}
+
+/// Naming scheme of the Ada sources of a project, as set by its `Naming`
+/// package.
+#[derive(Debug, Clone)]
+pub(crate) struct Naming {
+    pub spec_suffix: String,
+    pub dot_replacement: String,
+    pub casing: Casing,
+    /// Spec files of units named explicitly with `Spec (<unit>)`, keyed by
+    /// the lower case unit name.
+    pub specs: HashMap<String, String>,
+}
+
+impl Default for Naming {
+    fn default() -> Naming {
+        Naming {
+            spec_suffix: String::from(".ads"),
+            dot_replacement: String::from("-"),
+            casing: Casing::Lowercase,
+            specs: HashMap::new(),
+        }
+    }
+}
+
+impl Naming {
+    /// File name of the spec of `unit`.
+    fn spec_file(&self, unit: &str) -> String {
+        if let Some(file) = self.specs.get(&unit.to_lowercase()) {
+            return file.clone();
+        }
+        let base = match self.casing {
+            Casing::Lowercase => unit.to_lowercase(),
+            Casing::Uppercase => unit.to_uppercase(),
+            Casing::Mixedcase => String::from(unit),
+        };
+        format!(
+            "{}{}",
+            base.replace('.', &self.dot_replacement),
+            self.spec_suffix
+        )
+    }
+}
+
+/// Returns the spec file of `unit` among the project `sources`.
+fn find_spec(unit: &str, naming: &Naming, sources: &[PathBuf]) -> Option<PathBuf> {
+    let file = naming.spec_file(unit);
+    sources
+        .iter()
+        .find(|source| source.file_name().is_some_and(|name| name == file.as_str()))
+        .cloned()
+}
+
+fn generate_spec(source: &str, file: &Path, code: &mut String) -> Result<(), error::Error> {
+    let spec = Parser::new(source, file).spec()?;
+    Generator {
+        spec: &spec,
+        file,
+        needed: Vec::new(),
+    }
+    .generate(code)
+}
+
+/// Generates a Rust module with the `extern "C"` declarations and types for
+/// all C exports of the given interface units.
+pub(crate) fn generate(
+    library: &str,
+    units: &[String],
+    naming: &Naming,
+    sources: &[PathBuf],
+    project: &Path,
+) -> Result<String, error::Error> {
+    let mut code = format!("// Bindings for the Ada library `{library}`, generated by gpr.\n\n");
+    for unit in units {
+        let file = find_spec(unit, naming, sources).ok_or_else(|| {
+            error::Error::binding(
+                project,
+                0,
+                &format!("no spec found for interface unit {unit}"),
+            )
+        })?;
+        generate_spec(&fs::read_to_string(&file)?, &file, &mut code)?;
+    }
+    Ok(code.trim_end().to_string() + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(source: &str) -> Result<String, error::Error> {
+        let mut code = String::new();
+        generate_spec(source, Path::new("test.ads"), &mut code)?;
+        Ok(code)
+    }
+
+    #[test]
+    fn test_function_aspects() {
+        let code = bindings(
+            "with Interfaces.C;
+            package Test2 is
+               function Add (A : Interfaces.C.int;
+                             B : Interfaces.C.int) return Interfaces.C.int with
+                  Export,
+                  Convention => C,
+                  External_Name => \"test2_add\";
+            end Test2;",
+        )
+        .unwrap();
+        assert_eq!(
+            code,
+            "unsafe extern \"C\" {
+    /// Test2.Add
+    pub fn test2_add(a: core::ffi::c_int, b: core::ffi::c_int) -> core::ffi::c_int;
+}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_pragmas_and_types() {
+        let code = bindings(
+            "with Interfaces.C; use Interfaces.C;
+            package Shapes is
+               type Color is (Red, Green, Blue);
+               for Color use (Red => 1, Green => 2, Blue => 4);
+               pragma Convention (C, Color);
+               type Point is record
+                  X, Y : int;
+               end record with Convention => C;
+               type Point_Access is access all Point;
+               Quote : constant Character := '\"';
+               procedure Move (P : in out Point; Dx : int);
+               pragma Export (C, Move, \"shapes_move\");
+               function Tint (P : Point; Ref : Color) return Color;
+               pragma Export (Convention => C, Entity => Tint);
+               procedure Reset (P : Point_Access) with Export, Convention => C;
+               procedure Internal;
+            private
+               Unused : Integer := 0;
+            end Shapes;",
+        )
+        .unwrap();
+        assert!(code.contains("#[repr(C)]\n#[derive(Debug, Clone, Copy, PartialEq, Eq)]\npub enum Color {\n    Red = 1,\n    Green = 2,\n    Blue = 4,\n}"));
+        assert!(code.contains(
+            "pub struct Point {\n    pub x: core::ffi::c_int,\n    pub y: core::ffi::c_int,\n}"
+        ));
+        assert!(code.contains("pub type PointAccess = *mut Point;"));
+        assert!(code.contains("pub fn shapes_move(p: *mut Point, dx: core::ffi::c_int);"));
+        assert!(code.contains("pub fn tint(p: *const Point, r#ref: Color) -> Color;"));
+        assert!(code.contains("pub fn reset(p: PointAccess);"));
+        assert!(!code.contains("internal"));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let err = bindings(
+            "package P is
+               function F (X : Integer) return Integer with Export, Convention => C;
+            end P;",
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "test.ads:2: type Integer cannot be mapped, use a type from Interfaces.C"
+        );
+        let err = bindings(
+            "with Interfaces.C;
+            package P is
+               type R is record
+                  X : Interfaces.C.int;
+               end record;
+               procedure F (X : R) with Export, Convention => C;
+            end P;",
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "test.ads:3: type R does not have convention C"
+        );
+        assert!(bindings(
+            "package P is
+               procedure F with Export, Convention => Ada;
+            end P;",
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_opaque_component() {
+        let err = bindings(
+            "with Interfaces.C;
+            package P is
+               type H is private;
+               type R is record
+                  X : H;
+               end record with Convention => C;
+               procedure F (X : R) with Export, Convention => C;
+            private
+               type H is null record;
+            end P;",
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{err}"),
+            "test.ads:4: component X of R has the private type H, \
+             which can only be mapped through an access type"
+        );
+    }
+
+    #[test]
+    fn test_naming() {
+        let mut naming = Naming::default();
+        assert_eq!(naming.spec_file("Shapes.Util"), "shapes-util.ads");
+        naming.spec_suffix = String::from(".1.ada");
+        naming.dot_replacement = String::from("__");
+        naming.casing = Casing::Mixedcase;
+        assert_eq!(naming.spec_file("Shapes.Util"), "Shapes__Util.1.ada");
+        naming
+            .specs
+            .insert(String::from("shapes"), String::from("shapes_spec.ada"));
+        assert_eq!(naming.spec_file("Shapes"), "shapes_spec.ada");
+        let sources = [PathBuf::from("src/shapes_spec.ada")];
+        assert_eq!(
+            find_spec("SHAPES", &naming, &sources),
+            Some(sources[0].clone())
+        );
+        assert_eq!(find_spec("Other", &naming, &sources), None);
+    }
+
+    /// The generated bindings of `testdata/bindgen/shapes.ads`, compiled as
+    /// part of the tests.
+    #[allow(dead_code)]
+    mod shapes {
+        include!("../testdata/bindgen/shapes.rs");
+    }
+
+    #[test]
+    fn test_fixture() {
+        let code = generate(
+            "shapes",
+            &[String::from("Shapes")],
+            &Naming::default(),
+            &[PathBuf::from("testdata/bindgen/shapes.ads")],
+            Path::new("testdata/bindgen/shapes.gpr"),
+        )
+        .unwrap();
+        assert_eq!(code, include_str!("../testdata/bindgen/shapes.rs"));
+        let shape = shapes::Shape {
+            origin: shapes::Point { x: 1, y: 2 },
+            fill: shapes::Color::Blue,
+            data: std::ptr::null_mut(),
+        };
+        let copy = shape;
+        assert_eq!(copy.origin.y, shape.origin.y);
+        assert_eq!(shapes::Color::Blue as i32, 4);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(type_name("Point_Type"), "PointType");
+        assert_eq!(type_name("Shapes.Point"), "ShapesPoint");
+        assert_eq!(value_name("Type"), "r#type");
+        assert_eq!(value_name("Self"), "self_");
+        assert_eq!(value_name("Count"), "count");
+    }
+}
@@ -1,22 +1,25 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use serde_json::json;
 use std::{
-    collections::HashMap,
-    ffi::CString,
+    collections::{BTreeMap, HashMap},
     fmt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+#[cfg(not(gpr2_stub))]
+use std::{
+    ffi::{CStr, CString},
     os::raw::{c_char, c_int},
-    path::Path,
     ptr::null_mut,
-    sync::Mutex,
 };
 
 use super::error;
 
+#[cfg(not(gpr2_stub))]
 extern "C" {
     fn gpr2cinit();
     fn gpr2cfinal();
     fn gpr2_request(fun: c_int, request: *const c_char, answer: *mut *mut c_char) -> c_int;
-    #[allow(dead_code)] //CString::from_raw frees the answer already
     fn gpr2_free_answer(answer: *const c_char);
 }
 
@@ -24,14 +27,187 @@ lazy_static! {
     static ref GLOBAL_MUTEX: Mutex<i32> = Mutex::new(0i32);
 }
 
-fn raw_request(fun_id: i32, request: &str) -> std::result::Result<String, error::Error> {
-    let _lock = GLOBAL_MUTEX.lock().unwrap();
+// Paths that are not valid UTF-8 are exchanged with gpr2c as follows:
+//
+// - gpr2c reads and writes JSON strings byte by byte and uses their bytes
+//   as file names. Requests therefore contain the bytes of paths unchanged,
+//   only `"`, `\` and control characters are escaped. On Windows, where GNAT
+//   uses UTF-8 file names, paths must be valid Unicode.
+// - Answers are decoded by `answer_text`, which replaces each byte that is
+//   not part of valid UTF-8 with `\u0000` and its two hex digits. The answer
+//   is a NUL terminated C string and paths cannot contain NUL, so the escape
+//   is unambiguous. `path_of` restores the bytes of paths, `text_of`
+//   replaces them in other strings.
+
+/// JSON request to libgpr2, see above for the encoding of paths.
+struct Request(Vec<u8>);
+
+impl Request {
+    fn new() -> Request {
+        Request(vec![b'{'])
+    }
+
+    fn key(&mut self, name: &str) {
+        if self.0.len() > 1 {
+            self.0.push(b',');
+        }
+        self.0.extend(json!(name).to_string().bytes());
+        self.0.push(b':');
+    }
+
+    fn value(mut self, name: &str, value: serde_json::Value) -> Request {
+        self.key(name);
+        self.0.extend(value.to_string().bytes());
+        self
+    }
+
+    fn path(mut self, name: &str, path: &Path) -> std::result::Result<Request, error::Error> {
+        #[cfg(unix)]
+        let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+        #[cfg(not(unix))]
+        let bytes = path
+            .to_str()
+            .ok_or_else(|| error::Error::invalid_path(path, "is not valid Unicode"))?
+            .as_bytes();
+        if bytes.contains(&0) {
+            return Err(error::Error::invalid_path(path, "contains a NUL character"));
+        }
+        self.key(name);
+        self.0.push(b'"');
+        for byte in bytes {
+            match byte {
+                b'"' | b'\\' => self.0.extend([b'\\', *byte]),
+                0..=0x1f => self.0.extend(format!("\\u{byte:04x}").bytes()),
+                _ => self.0.push(*byte),
+            }
+        }
+        self.0.push(b'"');
+        Ok(self)
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.0.push(b'}');
+        self.0
+    }
+}
+
+#[cfg(gpr2_stub)]
+fn raw_request(_fun_id: i32, _request: Vec<u8>) -> std::result::Result<String, error::Error> {
+    Err(error::Error::Unavailable)
+}
+
+#[cfg(not(gpr2_stub))]
+fn raw_request(fun_id: i32, request: Vec<u8>) -> std::result::Result<String, error::Error> {
+    let request = CString::new(request).map_err(|_| {
+        error::Error::InvalidRequest(error::Exception::new(
+            1,
+            "NulError",
+            "request contains a NUL character",
+        ))
+    })?;
+    // A panic while holding the lock does not leave libgpr2 in an
+    // inconsistent state, so a poisoned lock can be used.
+    let _lock = GLOBAL_MUTEX
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
     let mut answer: *mut c_char = null_mut();
-    let request = CString::new(request).unwrap();
     unsafe {
         let _ = gpr2_request(fun_id as c_int, request.as_ptr(), &mut answer);
-        Ok(CString::from_raw(answer).into_string()?)
+        let text = answer_text(answer);
+        if !answer.is_null() {
+            gpr2_free_answer(answer);
+        }
+        text
+    }
+}
+
+/// Copies the answer of libgpr2, which may be null. Bytes that are not
+/// valid UTF-8 are escaped, see above.
+///
+/// # Safety
+///
+/// `answer` must be null or point to a NUL terminated string.
+#[cfg(not(gpr2_stub))]
+unsafe fn answer_text(answer: *const c_char) -> std::result::Result<String, error::Error> {
+    if answer.is_null() {
+        return Err(error::Error::invalid_response("libgpr2", "no answer"));
+    }
+    Ok(escape(CStr::from_ptr(answer).to_bytes()))
+}
+
+/// Escapes the bytes of `answer` that are not valid UTF-8.
+#[cfg_attr(gpr2_stub, allow(dead_code))]
+fn escape(answer: &[u8]) -> String {
+    let mut text = String::with_capacity(answer.len());
+    for chunk in answer.utf8_chunks() {
+        text.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\u0000{byte:02x}"));
+        }
     }
+    text
+}
+
+/// Bytes of a string of an answer with the escapes of `escape` restored.
+fn decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = (bytes[i] == 0)
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// Path of a string of an answer.
+fn path_of(text: &str) -> PathBuf {
+    let bytes = decode(text);
+    #[cfg(unix)]
+    return PathBuf::from(<std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(&bytes));
+    #[cfg(not(unix))]
+    return PathBuf::from(String::from_utf8_lossy(&bytes).into_owned());
+}
+
+/// Text of a string of an answer, bytes that are not valid UTF-8 are
+/// replaced.
+fn text_of(text: String) -> String {
+    if text.contains('\0') {
+        String::from_utf8_lossy(&decode(&text)).into_owned()
+    } else {
+        text
+    }
+}
+
+fn path<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<PathBuf, D::Error> {
+    Ok(path_of(&String::deserialize(deserializer)?))
+}
+
+fn paths<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<PathBuf>, D::Error> {
+    Ok(Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|text| path_of(text))
+        .collect())
+}
+
+fn optional_path<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<PathBuf>, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.map(|text| path_of(&text)))
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,35 +215,31 @@ fn raw_request(fun_id: i32, request: &str) -> std::result::Result<String, error:
 enum Result {
     Tree(Box<Tree>),
     Attribute(AttributeWrapper),
+    View(Box<ViewWrapper>),
+    Sources(SourcesWrapper),
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Tree {
     id: String,
     root_view: String,
-    #[allow(dead_code)]
     config_view: Option<String>,
     #[allow(dead_code)]
     runtime_view: Option<String>,
-    #[allow(dead_code)]
     target: String,
-    #[allow(dead_code)]
     canonical_target: String,
-    #[allow(dead_code)]
-    search_paths: Vec<String>,
-    #[allow(dead_code)]
+    #[serde(deserialize_with = "paths")]
+    search_paths: Vec<PathBuf>,
     src_subdirs: Option<String>,
     #[allow(dead_code)]
     subdirs: Option<String>,
     #[allow(dead_code)]
     build_path: Option<String>,
-    #[allow(dead_code)]
     views: Vec<String>,
-    #[allow(dead_code)]
     context: HashMap<String, String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 pub enum AttributeValue {
     Single(String),
@@ -90,11 +262,94 @@ pub struct Attribute {
     is_default: bool,
 }
 
+impl Attribute {
+    pub fn new(value: AttributeValue, is_default: bool) -> Attribute {
+        Attribute { value, is_default }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct AttributeWrapper {
     attribute: Attribute,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct View {
+    #[allow(dead_code)]
+    pub id: String,
+    pub name: String,
+    #[serde(deserialize_with = "path")]
+    pub path: PathBuf,
+    #[serde(deserialize_with = "path")]
+    pub dir: PathBuf,
+    pub kind: String,
+    /// Project file of the project extended by the view.
+    #[serde(default, deserialize_with = "optional_path")]
+    pub extended: Option<PathBuf>,
+    /// Whether the view is declared with `extends all`.
+    #[serde(default)]
+    pub extends_all: bool,
+    #[serde(flatten)]
+    pub dirs: Dirs,
+}
+
+/// Directories of a view as resolved by gpr2: absolute, relative to the
+/// project each value comes from and with recursive source directories
+/// expanded. Only reported by gpr2c.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Dirs {
+    #[serde(default, deserialize_with = "paths")]
+    pub source_dirs: Vec<PathBuf>,
+    #[serde(default, deserialize_with = "optional_path")]
+    pub object_dir: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_path")]
+    pub exec_dir: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_path")]
+    pub library_dir: Option<PathBuf>,
+    #[serde(default, deserialize_with = "optional_path")]
+    pub library_ali_dir: Option<PathBuf>,
+}
+
+impl Dirs {
+    /// Resolved value of the path attribute `name`, if gpr2c reports it.
+    pub fn get(&self, name: &str) -> Option<Vec<PathBuf>> {
+        let dir = match name.to_lowercase().as_str() {
+            "source_dirs" if !self.source_dirs.is_empty() => return Some(self.source_dirs.clone()),
+            "object_dir" => &self.object_dir,
+            "exec_dir" => &self.exec_dir,
+            "library_dir" => &self.library_dir,
+            "library_ali_dir" => &self.library_ali_dir,
+            _ => return None,
+        };
+        dir.clone().map(|dir| vec![dir])
+    }
+}
+
+impl View {
+    pub fn is_library(&self) -> bool {
+        matches!(
+            self.kind.to_lowercase().as_str(),
+            "k_library" | "k_aggregate_library"
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ViewWrapper {
+    view: View,
+}
+
+#[derive(Debug, Deserialize)]
+struct Source {
+    #[serde(deserialize_with = "path")]
+    path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcesWrapper {
+    sources: Vec<Source>,
+}
+
 #[derive(Debug, Deserialize)]
 struct EmptyResult {}
 
@@ -114,62 +369,252 @@ struct Answer {
 }
 
 pub fn initialize() {
+    #[cfg(not(gpr2_stub))]
     unsafe {
         gpr2cinit();
     }
 }
 
 pub fn finalize() {
+    #[cfg(not(gpr2_stub))]
     unsafe {
         gpr2cfinal();
     }
 }
 
-fn unwrap_result(answer: Answer) -> std::result::Result<Result, error::Error> {
+fn unwrap_result(raw_answer: &str) -> std::result::Result<Result, error::Error> {
+    let answer: Answer = serde_json::from_str(raw_answer)?;
     if let ResultWrapper::Valid(result) = answer.result {
         Ok(result)
     } else {
-        Err(
-            error::Error::from_status(answer.status, &answer.error_name, &answer.error_msg)
-                .unwrap(),
+        Err(error::Error::from_status(
+            answer.status,
+            &answer.error_name,
+            &text_of(answer.error_msg),
         )
+        .unwrap_or_else(|| error::Error::invalid_response("libgpr2", raw_answer)))
     }
 }
 
 impl Tree {
-    pub fn load(file: &Path) -> std::result::Result<Tree, error::Error> {
+    pub fn load(
+        file: &Path,
+        context: &BTreeMap<String, String>,
+        config: Option<&Path>,
+        src_subdirs: Option<&str>,
+    ) -> std::result::Result<Tree, error::Error> {
+        let mut request = Request::new()
+            .path("filename", file)?
+            .value("context", json!(context));
+        if let Some(config) = config {
+            request = request.path("config", config)?;
+        }
+        if let Some(src_subdirs) = src_subdirs {
+            request = request.value("src_subdirs", json!(src_subdirs));
+        }
+        let raw_answer = raw_request(1, request.finish())?;
+        match unwrap_result(&raw_answer)? {
+            Result::Tree(t) => Ok(*t),
+            _ => Err(error::Error::invalid_response("libgpr2", &raw_answer)),
+        }
+    }
+
+    pub fn context(&self) -> &HashMap<String, String> {
+        &self.context
+    }
+
+    pub fn src_subdirs(&self) -> Option<&str> {
+        self.src_subdirs.as_deref()
+    }
+
+    pub fn root_view(&self) -> &str {
+        &self.root_view
+    }
+
+    /// Directories projects are looked up in.
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    pub fn views(&self) -> &[String] {
+        &self.views
+    }
+
+    pub fn view(&self, view: &str) -> std::result::Result<View, error::Error> {
         let request = json!({
-            "filename": file.to_str().unwrap()
+            "tree_id": self.id,
+            "view_id": view
         })
         .to_string();
-        let raw_answer = raw_request(1, &request)?;
-        let answer: Answer = serde_json::from_str(&raw_answer)?;
-        match unwrap_result(answer)? {
-            Result::Tree(t) => Ok(*t),
-            _ => Err(error::Error::from_code(
-                error::Code::UnknownError,
-                "InvalidResponse",
-                &raw_answer,
-            )),
+        let raw_answer = raw_request(7, request.into_bytes())?;
+        match unwrap_result(&raw_answer)? {
+            Result::View(v) => Ok(v.view),
+            _ => Err(error::Error::invalid_response("libgpr2", &raw_answer)),
         }
     }
 
-    pub fn get_attribute(&self, name: &str) -> std::result::Result<Attribute, error::Error> {
+    /// Source files of `view`.
+    pub fn sources(&self, view: &str) -> std::result::Result<Vec<PathBuf>, error::Error> {
         let request = json!({
             "tree_id": self.id,
-            "view_id": self.root_view,
-            "name": name
+            "view_id": view
         })
         .to_string();
-        let raw_answer = raw_request(8, &request)?;
-        let answer: Answer = serde_json::from_str(&raw_answer)?;
-        match unwrap_result(answer)? {
-            Result::Attribute(a) => Ok(a.attribute),
-            _ => Err(error::Error::from_code(
-                error::Code::UnknownError,
-                "InvalidResponse",
-                &raw_answer,
-            )),
+        let raw_answer = raw_request(9, request.into_bytes())?;
+        match unwrap_result(&raw_answer)? {
+            Result::Sources(s) => Ok(s.sources.into_iter().map(|s| s.path).collect()),
+            _ => Err(error::Error::invalid_response("libgpr2", &raw_answer)),
+        }
+    }
+
+    pub fn config_view(&self) -> Option<&str> {
+        self.config_view.as_deref()
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn canonical_target(&self) -> &str {
+        &self.canonical_target
+    }
+
+    pub fn get_view_attribute(
+        &self,
+        view: &str,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> std::result::Result<Attribute, error::Error> {
+        let mut request = json!({
+            "tree_id": self.id,
+            "view_id": view,
+            "name": name
+        });
+        if let Some(pkg) = pkg {
+            request["pkg"] = json!(pkg);
+        }
+        if let Some(index) = index {
+            request["index"] = json!(index);
         }
+        let raw_answer = raw_request(8, request.to_string().into_bytes())?;
+        match unwrap_result(&raw_answer)? {
+            Result::Attribute(a) => Ok(Attribute {
+                value: match a.attribute.value {
+                    AttributeValue::Single(value) => AttributeValue::Single(text_of(value)),
+                    AttributeValue::List(values) => {
+                        AttributeValue::List(values.into_iter().map(text_of).collect())
+                    }
+                },
+                ..a.attribute
+            }),
+            _ => Err(error::Error::invalid_response("libgpr2", &raw_answer)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let request = Request::new()
+            .path("filename", Path::new("/prj/a \"b\"\\c\t.gpr"))
+            .unwrap()
+            .value("context", json!({"MODE": "debug"}))
+            .finish();
+        assert_eq!(
+            String::from_utf8(request).unwrap(),
+            r#"{"filename":"/prj/a \"b\"\\c\u0009.gpr","context":{"MODE":"debug"}}"#
+        );
+        assert!(matches!(
+            Request::new().path("filename", Path::new("/prj/a\0.gpr")),
+            Err(error::Error::InvalidPath { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_request_non_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let path = Path::new(OsStr::from_bytes(b"/prj/\xe9t\xe9.gpr"));
+        let request = Request::new().path("filename", path).unwrap().finish();
+        assert_eq!(request, b"{\"filename\":\"/prj/\xe9t\xe9.gpr\"}");
+    }
+
+    #[test]
+    fn test_unwrap_result() {
+        assert!(matches!(
+            unwrap_result(r#"{"result": {}, "status": 0, "error_msg": "", "error_name": ""}"#),
+            Err(error::Error::InvalidResponse { .. })
+        ));
+        match unwrap_result(
+            r#"{"result": {}, "status": 3, "error_msg": "boom", "error_name": "Program_Error"}"#,
+        ) {
+            Err(error::Error::Exception(e)) => {
+                assert_eq!(
+                    (e.status, e.name.as_str(), e.message.as_str()),
+                    (3, "Program_Error", "boom")
+                )
+            }
+            result => panic!("unexpected {result:?}"),
+        }
+        assert!(matches!(unwrap_result("{"), Err(error::Error::Json { .. })));
+    }
+
+    #[cfg(not(gpr2_stub))]
+    #[test]
+    fn test_answer_text() {
+        assert!(matches!(
+            unsafe { answer_text(null_mut()) },
+            Err(error::Error::InvalidResponse { .. })
+        ));
+        let answer = CString::new(b"{\"path\": \"\xe9\"}".to_vec()).unwrap();
+        assert_eq!(
+            unsafe { answer_text(answer.as_ptr()) }.unwrap(),
+            "{\"path\": \"\\u0000e9\"}"
+        );
+    }
+
+    #[test]
+    fn test_view_dirs() {
+        let Result::View(view) = unwrap_result(
+            r#"{"result": {"view": {"id": "1", "name": "P", "path": "/prj/p.gpr",
+                "dir": "/prj", "kind": "K_Library", "source_dirs": ["/prj/src", "/prj/src/a"],
+                "object_dir": "/prj/obj", "library_dir": "/prj/lib"}},
+                "status": 0, "error_msg": "", "error_name": ""}"#,
+        )
+        .unwrap() else {
+            panic!("not a view");
+        };
+        let dirs = &view.view.dirs;
+        assert_eq!(
+            dirs.get("Source_Dirs"),
+            Some(vec![PathBuf::from("/prj/src"), PathBuf::from("/prj/src/a")])
+        );
+        assert_eq!(
+            dirs.get("object_dir"),
+            Some(vec![PathBuf::from("/prj/obj")])
+        );
+        assert_eq!(dirs.get("exec_dir"), None);
+        assert_eq!(dirs.get("project_files"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_answer_non_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let answer = escape(
+            b"{\"result\": {\"view\": {\"id\": \"1\", \"name\": \"\xe9\", \
+              \"path\": \"/prj/\xe9t\xe9.gpr\", \"dir\": \"/prj\", \"kind\": \"K_Standard\"}}, \
+              \"status\": 0, \"error_msg\": \"\", \"error_name\": \"\"}",
+        );
+        let Result::View(view) = unwrap_result(&answer).unwrap() else {
+            panic!("{answer}");
+        };
+        assert_eq!(view.view.path, OsStr::from_bytes(b"/prj/\xe9t\xe9.gpr"));
+        assert_eq!(text_of(view.view.name), "\u{fffd}");
+        assert_eq!(path_of("/prj/\0zz\0"), Path::new("/prj/\0zz\0"));
     }
 }
@@ -0,0 +1,133 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::error;
+
+/// Toolchain configuration used to load and build a project.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Configuration {
+    /// Let libgpr2 auto-configure the toolchain each time a project is loaded.
+    #[default]
+    Auto,
+    /// Use an existing configuration project (`.cgpr`).
+    File(PathBuf),
+}
+
+impl Configuration {
+    /// Runs gprconfig to auto-configure the toolchain for `languages` and
+    /// writes the resulting configuration project to `output`.
+    ///
+    /// The runtime only applies to Ada, the target defaults to the native one.
+    pub fn generate(
+        output: &Path,
+        languages: &[&str],
+        target: Option<&str>,
+        runtime: Option<&str>,
+    ) -> Result<Configuration, error::Error> {
+        let mut cmd = Command::new("gprconfig");
+        cmd.arg("--batch").arg("-o").arg(output);
+        if let Some(target) = target {
+            cmd.arg(format!("--target={target}"));
+        }
+        for language in languages {
+            match runtime {
+                Some(runtime) if language.eq_ignore_ascii_case("ada") => {
+                    cmd.arg(format!("--config={language},,{runtime}"))
+                }
+                _ => cmd.arg(format!("--config={language}")),
+            };
+        }
+        let result = cmd.output()?;
+        if !result.status.success() {
+            return Err(error::Error::command(
+                "gprconfig",
+                &String::from_utf8_lossy(&result.stderr),
+            ));
+        }
+        Ok(Configuration::File(output.to_path_buf()))
+    }
+
+    /// Like [`Configuration::generate`] but reuses a configuration that has
+    /// already been generated, e.g. by a previous run of the build script.
+    ///
+    /// The file name of `output` is extended by a key derived from
+    /// `languages`, `target` and `runtime`, so changing any of them generates
+    /// a new configuration instead of reusing a stale one.
+    pub fn cached(
+        output: &Path,
+        languages: &[&str],
+        target: Option<&str>,
+        runtime: Option<&str>,
+    ) -> Result<Configuration, error::Error> {
+        let output = cache_file(output, languages, target, runtime);
+        if output.is_file() {
+            Ok(Configuration::File(output))
+        } else {
+            Configuration::generate(&output, languages, target, runtime)
+        }
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Configuration::Auto => None,
+            Configuration::File(file) => Some(file),
+        }
+    }
+}
+
+/// Inserts a hash of the configuration inputs into the file name of `output`.
+/// FNV-1a is used as it is stable across Rust versions, unlike the hasher of
+/// the standard library.
+fn cache_file(
+    output: &Path,
+    languages: &[&str],
+    target: Option<&str>,
+    runtime: Option<&str>,
+) -> PathBuf {
+    let mut languages: Vec<String> = languages.iter().map(|l| l.to_lowercase()).collect();
+    languages.sort();
+    languages.dedup();
+    let key = format!(
+        "{}\0{}\0{}",
+        languages.join(","),
+        target.unwrap_or_default(),
+        runtime.unwrap_or_default()
+    );
+    let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let mut name = output.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-{hash:016x}"));
+    if let Some(extension) = output.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    output.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_file() {
+        let output = Path::new("/out/auto.cgpr");
+        let file = cache_file(output, &["Ada", "C"], None, None);
+        assert_eq!(file.parent(), Some(Path::new("/out")));
+        assert_eq!(file.extension().unwrap(), "cgpr");
+        assert!(file
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("auto-"));
+        assert_eq!(file, cache_file(output, &["c", "ada"], None, None));
+        assert_ne!(file, cache_file(output, &["Ada"], None, None));
+        assert_ne!(
+            file,
+            cache_file(output, &["Ada", "C"], Some("arm-eabi"), None)
+        );
+        assert_ne!(file, cache_file(output, &["Ada", "C"], None, Some("light")));
+    }
+}
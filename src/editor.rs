@@ -0,0 +1,557 @@
+use std::path::{Path, PathBuf};
+
+use super::{
+    ast::{Index, Item, ItemKind, ProjectFile, Span, Term},
+    error,
+    gprgen::{quote, Expression},
+    lexer, parser,
+};
+
+/// Project file that is edited in place. Edits are applied directly to the
+/// source text, so comments, whitespace and casing outside of the edited
+/// declarations are preserved byte for byte.
+#[derive(Debug, Clone)]
+pub struct ProjectEditor {
+    file: PathBuf,
+    source: String,
+    ast: ProjectFile,
+}
+
+/// Compares `with` paths, which may omit the `.gpr` extension.
+fn same_project(a: &str, b: &str) -> bool {
+    let strip = |p: &str| {
+        let p = p.to_lowercase();
+        p.strip_suffix(".gpr").map(String::from).unwrap_or(p)
+    };
+    strip(a) == strip(b)
+}
+
+fn same_index(index: &Option<Index>, expected: Option<&str>) -> bool {
+    match (index, expected) {
+        (None, None) => true,
+        (Some(Index::Value(literal)), Some(expected)) => {
+            literal.value.eq_ignore_ascii_case(expected)
+        }
+        (Some(Index::Others), Some(expected)) => expected.eq_ignore_ascii_case("others"),
+        _ => false,
+    }
+}
+
+fn declaration(name: &str, index: Option<&str>, value: &Expression) -> String {
+    match index {
+        Some(index) if index.eq_ignore_ascii_case("others") => {
+            format!("for {name} (others) use {value};")
+        }
+        Some(index) => format!("for {name} ({}) use {value};", quote(index)),
+        None => format!("for {name} use {value};"),
+    }
+}
+
+impl ProjectEditor {
+    /// Reads and parses the project file `file`.
+    pub fn open(file: &Path) -> Result<ProjectEditor, error::Error> {
+        ProjectEditor::parse(&std::fs::read_to_string(file)?, file)
+    }
+
+    /// Parses `source` as the contents of the project file `file`.
+    pub fn parse(source: &str, file: &Path) -> Result<ProjectEditor, error::Error> {
+        Ok(ProjectEditor {
+            file: file.to_path_buf(),
+            source: String::from(source),
+            ast: parser::parse(source, file)?,
+        })
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// The edited source text.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Writes the edited source back to the project file.
+    pub fn write(&self) -> Result<(), error::Error> {
+        self.write_to(&self.file)
+    }
+
+    pub fn write_to(&self, file: &Path) -> Result<(), error::Error> {
+        Ok(std::fs::write(file, &self.source)?)
+    }
+
+    /// Replaces the source between `start` and `end` with `text`. The edit
+    /// is undone if the result is not a valid project file.
+    fn splice(&mut self, start: usize, end: usize, text: &str) -> Result<(), error::Error> {
+        let mut source = self.source.clone();
+        source.replace_range(start..end, text);
+        self.ast = parser::parse(&source, &self.file)?;
+        self.source = source;
+        Ok(())
+    }
+
+    fn line_start(&self, offset: usize) -> usize {
+        self.source[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    /// Offset of the line following the one containing `offset`.
+    fn next_line(&self, offset: usize) -> usize {
+        self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |i| offset + i + 1)
+    }
+
+    /// Inserts `line` after the line containing `offset`, so that trailing
+    /// comments stay with their declarations.
+    fn insert_line(&mut self, offset: usize, line: &str) -> Result<(), error::Error> {
+        let at = self.next_line(offset);
+        let text = if self.source[..at].ends_with('\n') {
+            format!("{line}\n")
+        } else {
+            format!("\n{line}")
+        };
+        self.splice(at, at, &text)
+    }
+
+    fn indentation(&self, offset: usize) -> &str {
+        let start = self.line_start(offset);
+        let prefix = &self.source[start..offset];
+        &prefix[..prefix.len() - prefix.trim_start().len()]
+    }
+
+    /// Removes the source of `span`, together with its whole line and a
+    /// trailing comment if nothing else is on it.
+    fn remove_span(&mut self, span: Span) -> Result<(), error::Error> {
+        let start = self.line_start(span.start);
+        let line_end = self.next_line(span.end);
+        let rest = self.source[span.end..line_end].trim();
+        if self.source[start..span.start].trim().is_empty()
+            && (rest.is_empty() || rest.starts_with("--"))
+        {
+            self.splice(start, line_end, "")
+        } else {
+            self.splice(span.start, span.end, "")
+        }
+    }
+
+    /// Offset of the `end` keyword closing the declaration in `span`.
+    fn end_keyword(&self, span: Span) -> usize {
+        lexer::tokenize(&self.source[span.start..span.end])
+            .iter()
+            .rev()
+            .find(|t| t.is_keyword("end"))
+            .map_or(span.end, |t| span.start + t.offset)
+    }
+
+    /// Items and span of the project or of one of its packages.
+    fn scope(&self, package: Option<&str>) -> Option<(&[Item], Span)> {
+        match package {
+            None => Some((&self.ast.items, self.ast.span)),
+            Some(package) => self.ast.items.iter().find_map(|item| match &item.kind {
+                ItemKind::Package {
+                    name,
+                    renames: None,
+                    items,
+                    ..
+                } if name.last().eq_ignore_ascii_case(package) => {
+                    Some((items.as_slice(), item.span))
+                }
+                _ => None,
+            }),
+        }
+    }
+
+    /// Declarations of the attribute outside of case statements.
+    fn declarations(&self, package: Option<&str>, name: &str, index: Option<&str>) -> Vec<&Item> {
+        let Some((items, _)) = self.scope(package) else {
+            return Vec::new();
+        };
+        items
+            .iter()
+            .filter(|item| match &item.kind {
+                ItemKind::Attribute {
+                    name: n, index: i, ..
+                } => n.last().eq_ignore_ascii_case(name) && same_index(i, index),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Whether the attribute is only declared in case statements, where it
+    /// cannot be changed without knowing the scenario.
+    fn declared_in_case(&self, package: Option<&str>, name: &str, index: Option<&str>) -> bool {
+        fn find(items: &[Item], name: &str, index: Option<&str>, in_case: bool) -> bool {
+            items.iter().any(|item| match &item.kind {
+                ItemKind::Attribute {
+                    name: n, index: i, ..
+                } => in_case && n.last().eq_ignore_ascii_case(name) && same_index(i, index),
+                ItemKind::Case { alternatives, .. } => alternatives
+                    .iter()
+                    .any(|a| find(&a.items, name, index, true)),
+                _ => false,
+            })
+        }
+        self.scope(package)
+            .is_some_and(|(items, _)| find(items, name, index, false))
+    }
+
+    /// Inserts the declaration `text` at the end of the project or package.
+    fn insert(&mut self, package: Option<&str>, text: &str) -> Result<(), error::Error> {
+        match self.scope(package) {
+            Some((items, span)) => match items.last() {
+                Some(last) => {
+                    let indent = self.indentation(last.span.start).to_owned();
+                    let text = text.replace('\n', &format!("\n{indent}"));
+                    self.insert_line(last.span.end, &format!("{indent}{text}"))
+                }
+                None => {
+                    let end = self.end_keyword(span);
+                    let indent = format!("{}   ", self.indentation(end));
+                    let text = text.replace('\n', &format!("\n{indent}"));
+                    let start = self.line_start(end);
+                    self.splice(start, start, &format!("{indent}{text}\n"))
+                }
+            },
+            None => {
+                let package = package.unwrap_or_default();
+                self.insert(
+                    None,
+                    &format!("package {package} is\n   {text}\nend {package};"),
+                )
+            }
+        }
+    }
+
+    /// Sets the value of an attribute of the project or of one of its
+    /// packages. An existing declaration is replaced, otherwise the attribute
+    /// is declared at the end of the project or package.
+    pub fn set_attribute<E: Into<Expression>>(
+        &mut self,
+        package: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+        value: E,
+    ) -> Result<(), error::Error> {
+        let value = value.into();
+        let span = self
+            .declarations(package, name, index)
+            .last()
+            .map(|item| match &item.kind {
+                ItemKind::Attribute { value, .. } => value.span,
+                _ => item.span,
+            });
+        match span {
+            Some(span) => self.splice(span.start, span.end, &value.to_string()),
+            None if self.declared_in_case(package, name, index) => {
+                Err(error::Error::declared_in_case(&self.file, name))
+            }
+            None => self.insert(package, &declaration(name, index, &value)),
+        }
+    }
+
+    /// Appends `values` to a list attribute, which is declared if it does
+    /// not exist yet.
+    pub fn add_values(
+        &mut self,
+        package: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+        values: &[&str],
+    ) -> Result<(), error::Error> {
+        let list: Vec<String> = values.iter().map(|v| quote(v)).collect();
+        let value = self
+            .declarations(package, name, index)
+            .last()
+            .and_then(|item| match &item.kind {
+                ItemKind::Attribute { value, .. } => Some(value.clone()),
+                _ => None,
+            });
+        let Some(value) = value else {
+            return self.set_attribute(package, name, index, Expression::from(values));
+        };
+        match value.terms.as_slice() {
+            [Term::List(elements, span)] => match elements.last() {
+                Some(last) => {
+                    let end = last.span.end;
+                    self.splice(end, end, &format!(", {}", list.join(", ")))
+                }
+                None => self.splice(span.start, span.end, &format!("({})", list.join(", "))),
+            },
+            _ => {
+                let end = value.span.end;
+                self.splice(end, end, &format!(" & ({})", list.join(", ")))
+            }
+        }
+    }
+
+    /// Removes string literals with one of `values` from the lists of a list
+    /// attribute. Returns whether the project has been changed.
+    pub fn remove_values(
+        &mut self,
+        package: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+        values: &[&str],
+    ) -> Result<bool, error::Error> {
+        let mut changed = false;
+        loop {
+            let range = self
+                .declarations(package, name, index)
+                .into_iter()
+                .filter_map(|item| match &item.kind {
+                    ItemKind::Attribute { value, .. } => Some(value),
+                    _ => None,
+                })
+                .flat_map(|value| &value.terms)
+                .find_map(|term| {
+                    let Term::List(elements, _) = term else {
+                        return None;
+                    };
+                    let i = elements.iter().position(|e| {
+                        matches!(e.terms.as_slice(), [Term::String(s)] if values.contains(&s.value.as_str()))
+                    })?;
+                    Some(match (i.checked_sub(1), elements.get(i + 1)) {
+                        (_, Some(next)) => (elements[i].span.start, next.span.start),
+                        (Some(previous), None) => {
+                            (elements[previous].span.end, elements[i].span.end)
+                        }
+                        (None, None) => (elements[i].span.start, elements[i].span.end),
+                    })
+                });
+            let Some((start, end)) = range else {
+                return Ok(changed);
+            };
+            self.splice(start, end, "")?;
+            changed = true;
+        }
+    }
+
+    /// Removes all declarations of an attribute outside of case statements.
+    /// Returns whether the project has been changed.
+    pub fn remove_attribute(
+        &mut self,
+        package: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<bool, error::Error> {
+        let mut changed = false;
+        while let Some(span) = self
+            .declarations(package, name, index)
+            .first()
+            .map(|i| i.span)
+        {
+            self.remove_span(span)?;
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    /// Adds a `with` clause for `path` unless the project already imports
+    /// it. Returns whether the project has been changed.
+    pub fn add_with(&mut self, path: &str) -> Result<bool, error::Error> {
+        let withs = &self.ast.withs;
+        if withs
+            .iter()
+            .flat_map(|w| w.paths.iter())
+            .any(|p| same_project(&p.value, path))
+        {
+            return Ok(false);
+        }
+        let clause = format!("with {};", quote(path));
+        match withs.last() {
+            Some(last) => {
+                let end = last.span.end;
+                self.insert_line(end, &clause)?
+            }
+            None => {
+                let start = self.ast.span.start;
+                self.splice(start, start, &format!("{clause}\n\n"))?
+            }
+        }
+        Ok(true)
+    }
+
+    /// Removes `path` from the `with` clauses. Returns whether the project
+    /// has been changed.
+    pub fn remove_with(&mut self, path: &str) -> Result<bool, error::Error> {
+        let mut found = None;
+        for with in &self.ast.withs {
+            if let Some(i) = with.paths.iter().position(|p| same_project(&p.value, path)) {
+                let paths = &with.paths;
+                found = Some(match (i.checked_sub(1), paths.get(i + 1)) {
+                    (None, None) => (with.span, true),
+                    (_, Some(next)) => (
+                        Span {
+                            start: paths[i].span.start,
+                            end: next.span.start,
+                            ..paths[i].span
+                        },
+                        false,
+                    ),
+                    (Some(previous), None) => (
+                        Span {
+                            start: paths[previous].span.end,
+                            end: paths[i].span.end,
+                            ..paths[i].span
+                        },
+                        false,
+                    ),
+                });
+                break;
+            }
+        }
+        match found {
+            Some((span, true)) => self.remove_span(span)?,
+            Some((span, false)) => self.splice(span.start, span.end, "")?,
+            None => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+--  Release build of Lib.
+with \"common\";  -- shared settings
+
+library project Lib is
+
+   for Source_Dirs use (\"src\",  \"gen\"); -- generated code
+   for LIBRARY_VERSION use \"libLib.so.1\";
+
+   case Common.Mode is
+      when \"debug\" =>
+         for Library_Name use \"lib_debug\";
+      when others =>
+         for Library_Name use \"lib\";
+   end case;
+
+   package Compiler is
+      --  keep these
+      for Default_Switches (\"Ada\") use (\"-O2\");
+   end Compiler;
+
+end Lib;
+";
+
+    fn editor() -> ProjectEditor {
+        ProjectEditor::parse(SOURCE, Path::new("lib.gpr")).unwrap()
+    }
+
+    #[test]
+    fn test_set_attribute() {
+        let mut editor = editor();
+        editor
+            .set_attribute(None, "Library_Version", None, "libLib.so.2")
+            .unwrap();
+        assert_eq!(
+            editor.source(),
+            SOURCE.replace("\"libLib.so.1\"", "\"libLib.so.2\"")
+        );
+        editor
+            .set_attribute(Some("compiler"), "Default_Switches", Some("C"), ["-O1"])
+            .unwrap();
+        assert!(editor.source().contains(
+            "      for Default_Switches (\"Ada\") use (\"-O2\");\n      for Default_Switches (\"C\") use (\"-O1\");\n   end Compiler;"
+        ));
+        editor
+            .set_attribute(Some("Binder"), "Switches", Some("others"), ["-E"])
+            .unwrap();
+        assert!(editor.source().ends_with(
+            "   end Compiler;\n   package Binder is\n      for Switches (others) use (\"-E\");\n   end Binder;\n\nend Lib;\n"
+        ));
+        let mut empty = ProjectEditor::parse("project P is\nend P;\n", Path::new("p.gpr")).unwrap();
+        empty
+            .set_attribute(None, "Object_Dir", None, "obj")
+            .unwrap();
+        assert_eq!(
+            empty.source(),
+            "project P is\n   for Object_Dir use \"obj\";\nend P;\n"
+        );
+        assert!(matches!(
+            editor.set_attribute(None, "Library_Name", None, "other"),
+            Err(error::Error::DeclaredInCase { .. })
+        ));
+    }
+
+    #[test]
+    fn test_values() {
+        let mut editor = editor();
+        editor
+            .add_values(None, "source_dirs", None, &["extra"])
+            .unwrap();
+        assert!(editor
+            .source()
+            .contains("for Source_Dirs use (\"src\",  \"gen\", \"extra\"); -- generated code"));
+        assert!(editor
+            .remove_values(None, "Source_Dirs", None, &["src", "extra"])
+            .unwrap());
+        assert!(editor
+            .source()
+            .contains("for Source_Dirs use (\"gen\"); -- generated code"));
+        assert!(!editor
+            .remove_values(None, "Source_Dirs", None, &["missing"])
+            .unwrap());
+        editor
+            .add_values(None, "Languages", None, &["Ada", "C"])
+            .unwrap();
+        assert!(editor
+            .source()
+            .contains("   for LIBRARY_VERSION use \"libLib.so.1\";\n\n   case Common.Mode is"));
+        assert!(editor
+            .source()
+            .contains("   end Compiler;\n   for Languages use (\"Ada\", \"C\");\n\nend Lib;"));
+    }
+
+    #[test]
+    fn test_remove_values_later_declaration() {
+        let mut editor = ProjectEditor::parse(
+            "project P is\n   for Source_Dirs use (\"a\");\n   for Source_Dirs use Project'Source_Dirs & (\"c\");\nend P;\n",
+            Path::new("p.gpr"),
+        )
+        .unwrap();
+        assert!(editor
+            .remove_values(None, "Source_Dirs", None, &["a"])
+            .unwrap());
+        assert_eq!(
+            editor.source(),
+            "project P is\n   for Source_Dirs use ();\n   for Source_Dirs use Project'Source_Dirs & (\"c\");\nend P;\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_attribute() {
+        let mut editor = editor();
+        assert!(editor
+            .remove_attribute(None, "library_version", None)
+            .unwrap());
+        assert_eq!(
+            editor.source(),
+            SOURCE.replace("   for LIBRARY_VERSION use \"libLib.so.1\";\n", "")
+        );
+        assert!(!editor.remove_attribute(None, "main", None).unwrap());
+    }
+
+    #[test]
+    fn test_with() {
+        let mut editor = editor();
+        assert!(!editor.add_with("common.gpr").unwrap());
+        assert!(editor.add_with("../shared/util.gpr").unwrap());
+        assert!(editor.source().starts_with(
+            "--  Release build of Lib.\nwith \"common\";  -- shared settings\nwith \"../shared/util.gpr\";\n\n"
+        ));
+        assert!(editor.remove_with("../shared/util").unwrap());
+        assert_eq!(editor.source(), SOURCE);
+        assert!(editor.remove_with("common").unwrap());
+        assert!(editor
+            .source()
+            .starts_with("--  Release build of Lib.\n\nlibrary project Lib is"));
+        assert!(editor.add_with("common").unwrap());
+        assert!(editor.source().starts_with(
+            "--  Release build of Lib.\n\nwith \"common\";\n\nlibrary project Lib is"
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+/// Generates a Rust module that initializes the standalone library `name`
+/// exactly once and keeps it initialized until the process exits, unless it
+/// is finalized explicitly. If the library is initialized automatically when
+/// it is loaded (`auto_init`) only the finalization is generated.
+pub(crate) fn generate(name: &str, auto_init: bool) -> String {
+    let mut code = String::new();
+    let _ = writeln!(
+        code,
+        "// Elaboration guard for the Ada library `{name}`, generated by gpr.\n"
+    );
+    code.push_str("unsafe extern \"C\" {\n");
+    if !auto_init {
+        let _ = writeln!(code, "    fn {name}init();");
+    }
+    let _ = writeln!(code, "    fn {name}final();");
+    code.push_str("}\n\n");
+    code.push_str(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unelaborated,
+    Elaborated,
+    Finalized,
+}
+
+static STATE: std::sync::Mutex<State> = std::sync::Mutex::new(State::Unelaborated);
+
+",
+    );
+    let _ = writeln!(
+        code,
+        "/// Shows that the Ada library `{name}` is elaborated.
+///
+/// The library is initialized when the first guard is acquired and stays
+/// initialized until the process exits or [`finalize`] is called."
+    );
+    code.push_str(
+        "#[derive(Debug, Clone, Copy)]
+pub struct Guard {
+    _private: (),
+}
+
+impl Guard {
+    /// Initializes the library if it is not initialized yet. Returns `None`
+    /// once the library has been finalized.
+    pub fn acquire() -> Option<Guard> {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            State::Unelaborated => {
+",
+    );
+    if !auto_init {
+        let _ = writeln!(code, "                unsafe {{ {name}init() }};");
+    }
+    code.push_str(
+        "                *state = State::Elaborated;
+            }
+            State::Elaborated => {}
+            State::Finalized => return None,
+        }
+        Some(Guard { _private: () })
+    }
+}
+
+",
+    );
+    let _ = writeln!(
+        code,
+        "/// Finalizes the Ada library `{name}` if it is elaborated. The library
+/// cannot be used afterwards, so this must only be called once no code of it
+/// runs any more, e.g. at the end of `main`."
+    );
+    code.push_str(
+        "pub fn finalize() {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if *state == State::Elaborated {
+",
+    );
+    let _ = writeln!(code, "        unsafe {{ {name}final() }};");
+    code.push_str(
+        "    }
+    *state = State::Finalized;
+}
+",
+    );
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let code = generate("test2", false);
+        assert!(code.contains("    fn test2init();\n    fn test2final();\n"));
+        assert!(code.contains("unsafe { test2init() };"));
+        assert!(code.contains("unsafe { test2final() };"));
+        assert!(code.contains("pub struct Guard"));
+        assert!(!code.contains("impl Drop"));
+    }
+
+    /// The guard generated for the library `fixture`, compiled as part of
+    /// the tests.
+    mod fixture {
+        include!("../testdata/rust/fixture_elaboration.rs");
+    }
+
+    /// Stands in for the initialization and finalization functions of the
+    /// library `fixture`.
+    mod fixture_library {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        pub static INIT: AtomicUsize = AtomicUsize::new(0);
+        pub static FINAL: AtomicUsize = AtomicUsize::new(0);
+
+        #[no_mangle]
+        extern "C" fn fixtureinit() {
+            INIT.fetch_add(1, Ordering::SeqCst);
+        }
+
+        #[no_mangle]
+        extern "C" fn fixturefinal() {
+            FINAL.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_fixture() {
+        use std::sync::atomic::Ordering;
+
+        assert_eq!(
+            generate("fixture", false),
+            include_str!("../testdata/rust/fixture_elaboration.rs")
+        );
+        assert!(fixture::Guard::acquire().is_some());
+        assert!(fixture::Guard::acquire().is_some());
+        assert_eq!(fixture_library::INIT.load(Ordering::SeqCst), 1);
+        assert_eq!(fixture_library::FINAL.load(Ordering::SeqCst), 0);
+        fixture::finalize();
+        fixture::finalize();
+        assert_eq!(fixture_library::FINAL.load(Ordering::SeqCst), 1);
+        assert!(fixture::Guard::acquire().is_none());
+    }
+
+    #[test]
+    fn test_generate_auto_init() {
+        let code = generate("test2", true);
+        assert!(!code.contains("test2init"));
+        assert!(code.contains("unsafe { test2final() };"));
+    }
+}
@@ -2,22 +2,135 @@ use serde_json::Error as JsonError;
 use std::{fmt::Display, path::Path};
 use thiserror::Error as ThisError;
 
-#[derive(Debug, Display)]
-pub enum Code {
-    InvalidRequest,
-    CallError,
-    IOError,
-    UnknownError,
+/// Location in a project file that an error refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: Option<usize>,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Exception reported by libgpr2, or the equivalent error of another
+/// backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exception {
+    /// Status of the answer of the C binding, 1 for invalid requests, 2
+    /// for errors of the call and 3 for unknown errors.
+    pub status: i32,
+    /// Name of the exception, e.g. `GPR2.Project_Error`.
+    pub name: String,
+    pub message: String,
+    /// Location of the first `file:line[:column]` in the message.
+    pub location: Option<Location>,
+}
+
+impl Display for Exception {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (status {}): {}",
+            self.name, self.status, self.message
+        )
+    }
+}
+
+impl Exception {
+    pub fn new(status: i32, name: &str, message: &str) -> Exception {
+        Exception {
+            status,
+            name: String::from(name),
+            message: String::from(message),
+            location: location(message),
+        }
+    }
+}
+
+/// Splits a leading decimal number off `text`.
+fn number(text: &str) -> Option<(usize, &str)> {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    text[..digits].parse().ok().map(|n| (n, &text[digits..]))
+}
+
+/// Splits a leading `file:line[:column]` off a line of a message.
+fn split_location(text: &str) -> Option<(Location, &str)> {
+    text.match_indices(':').find_map(|(i, _)| {
+        let file = text[..i].trim();
+        let (line, rest) = number(&text[i + 1..])?;
+        if file.is_empty() || !(rest.is_empty() || rest.starts_with(':')) {
+            return None;
+        }
+        let column = rest
+            .strip_prefix(':')
+            .and_then(number)
+            .filter(|(_, rest)| rest.is_empty() || rest.starts_with(':'));
+        let rest = column.map_or(rest, |(_, rest)| rest);
+        Some((
+            Location {
+                file: String::from(file),
+                line,
+                column: column.map(|(column, _)| column),
+            },
+            rest.strip_prefix(':').unwrap_or(rest),
+        ))
+    })
+}
+
+/// Finds the first `file:line[:column]` in `message`.
+fn location(message: &str) -> Option<Location> {
+    message
+        .lines()
+        .find_map(|text| split_location(text).map(|(location, _)| location))
 }
 
 #[derive(Debug, ThisError)]
 pub enum Error {
-    #[error("gpr ({code}) {name}: {message}")]
-    Gpr {
-        code: Code,
-        name: String,
-        message: String,
+    #[error("project file not found: {0}")]
+    ProjectNotFound(Exception),
+    #[error("invalid project: {0}")]
+    InvalidProject(Exception),
+    #[error("invalid runtime: {0}")]
+    InvalidRuntime(Exception),
+    #[error("attribute not found: {0}")]
+    AttributeNotFound(Exception),
+    #[error("view not found: {0}")]
+    ViewNotFound(Exception),
+    #[error("invalid request: {0}")]
+    InvalidRequest(Exception),
+    /// Exception of libgpr2 that is none of the above.
+    #[error("libgpr2: {0}")]
+    Exception(Exception),
+    #[error("invalid response from {backend}: {response}")]
+    InvalidResponse { backend: String, response: String },
+    #[error(
+        "libgpr2 not available, gpr has been built without it (DOCS_RS or the `stub` feature)"
+    )]
+    Unavailable,
+    #[error("{file} is an aggregate project, use aggregated() or libraries() instead")]
+    NoLibrary { file: String },
+    #[error("project tree has no configuration view")]
+    NoConfiguration,
+    #[error("{name} is not set, it is only set for build scripts")]
+    NoBuildEnv { name: String },
+    #[error("OUT_DIR {path} is not located inside a cargo target directory")]
+    InvalidOutDir { path: String },
+    #[error("{backend} does not report {what} and the native evaluation of the project failed: {source}")]
+    NotReported {
+        backend: String,
+        what: String,
+        source: Box<Error>,
     },
+    #[error("GPR_RUST_BACKEND must be libgpr2, gprinspect or native, not {value}")]
+    InvalidBackend { value: String },
     #[error(transparent)]
     Io {
         #[from]
@@ -39,40 +152,131 @@ pub enum Error {
         name: String,
         value: String,
     },
+    #[error("{name} is declared in a case statement in {file}")]
+    DeclaredInCase { file: String, name: String },
     #[error("invalid attribute value {value} for attribute {name} in {file}")]
     InvalidAttributeValue {
         file: String,
         name: String,
         value: String,
     },
+    #[error(
+        "invalid value {value} for scenario variable {name} in {file}, expected one of: {allowed}"
+    )]
+    InvalidScenarioValue {
+        file: String,
+        name: String,
+        value: String,
+        allowed: String,
+    },
+    #[error("{command} failed: {message}")]
+    Command { command: String, message: String },
+    #[error("no {language} compiler found for target {target} with runtime {runtime} (available: {available}), make sure a matching GNAT toolchain is in PATH")]
+    NoCompiler {
+        language: String,
+        target: String,
+        runtime: String,
+        available: String,
+    },
+    #[error("{file}:{line}: {message}")]
+    Binding {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    #[error("{file}:{line}:{column}: {message}")]
+    Syntax {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("invalid path {path}: {message}")]
+    InvalidPath { path: String, message: String },
+    #[error("unknown typed scenario variable {name} in {file}")]
+    UnknownScenarioVariable { file: String, name: String },
 }
 
 impl Error {
-    pub fn from_code(code: Code, name: &str, message: &str) -> Error {
-        Error::Gpr {
-            code,
-            name: String::from(name),
-            message: String::from(message),
+    /// Error for an answer of the C binding with a status other than 0.
+    pub fn from_status(status: i32, name: &str, message: &str) -> Option<Error> {
+        if status == 0 {
+            return None;
         }
+        Some(Error::from_exception(Exception::new(status, name, message)))
     }
 
-    pub fn from_status(status: i32, name: &str, message: &str) -> Option<Error> {
-        match status {
-            0 => None,
-            1 => Some(Error::from_code(Code::InvalidRequest, name, message)),
-            2 => Some(Error::from_code(Code::CallError, name, message)),
-            3 => Some(Error::from_code(Code::UnknownError, name, message)),
-            _ => Some(Error::from_code(
-                Code::UnknownError,
-                "UnknownError",
-                "unknown code",
-            )),
+    /// Classifies `exception` by its status and the name of the exception.
+    /// Generic exceptions like `GPR2.Project_Error` are classified by their
+    /// message, without the locations it refers to.
+    pub fn from_exception(exception: Exception) -> Error {
+        let name = exception.name.to_lowercase();
+        let message: String = exception
+            .message
+            .lines()
+            .map(|text| split_location(text).map_or(text, |(_, rest)| rest))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase();
+        let not_found = [
+            "not found",
+            "does not exist",
+            "cannot find",
+            "not defined",
+            "undefined",
+        ]
+        .iter()
+        .any(|m| message.contains(m));
+        if exception.status == 1 || name.contains("invalid_request") {
+            Error::InvalidRequest(exception)
+        } else if name.contains("runtime") {
+            Error::InvalidRuntime(exception)
+        } else if name.contains("attribute") {
+            Error::AttributeNotFound(exception)
+        } else if name.contains("view") {
+            Error::ViewNotFound(exception)
+        } else if name.contains("name_error") {
+            Error::ProjectNotFound(exception)
+        } else if not_found && message.contains("attribute") {
+            Error::AttributeNotFound(exception)
+        } else if not_found && message.contains("view") {
+            Error::ViewNotFound(exception)
+        } else if message.contains("runtime") {
+            Error::InvalidRuntime(exception)
+        } else if not_found && (message.contains("project") || message.contains(".gpr")) {
+            Error::ProjectNotFound(exception)
+        } else if name.contains("project_error") || name.contains("processing_error") {
+            Error::InvalidProject(exception)
+        } else {
+            Error::Exception(exception)
+        }
+    }
+
+    /// The exception behind the error, if it comes from libgpr2 or one of
+    /// the other backends.
+    pub fn exception(&self) -> Option<&Exception> {
+        match self {
+            Error::ProjectNotFound(e)
+            | Error::InvalidProject(e)
+            | Error::InvalidRuntime(e)
+            | Error::AttributeNotFound(e)
+            | Error::ViewNotFound(e)
+            | Error::InvalidRequest(e)
+            | Error::Exception(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn invalid_response(backend: &str, response: &str) -> Error {
+        Error::InvalidResponse {
+            backend: String::from(backend),
+            response: String::from(response),
         }
     }
 
     pub fn invalid_attribute(file: &Path, attribute: &str, value: &str) -> Error {
         Error::InvalidAttribute {
-            file: String::from(file.to_str().unwrap()),
+            file: file.display().to_string(),
             name: String::from(attribute),
             value: String::from(value),
         }
@@ -84,9 +288,213 @@ impl Error {
         value: &crate::binding::AttributeValue,
     ) -> Error {
         Error::InvalidAttributeValue {
-            file: String::from(file.to_str().unwrap()),
+            file: file.display().to_string(),
             name: String::from(attribute),
             value: format!("{value}"),
         }
     }
+
+    pub fn invalid_scenario_value(
+        file: &Path,
+        name: &str,
+        value: &str,
+        allowed: &[String],
+    ) -> Error {
+        Error::InvalidScenarioValue {
+            file: file.display().to_string(),
+            name: String::from(name),
+            value: String::from(value),
+            allowed: allowed.join(", "),
+        }
+    }
+
+    pub fn unknown_scenario_variable(file: &Path, name: &str) -> Error {
+        Error::UnknownScenarioVariable {
+            file: file.display().to_string(),
+            name: String::from(name),
+        }
+    }
+
+    pub fn command(command: &str, message: &str) -> Error {
+        Error::Command {
+            command: String::from(command),
+            message: String::from(message),
+        }
+    }
+
+    pub fn no_compiler(
+        language: &str,
+        target: Option<&str>,
+        runtime: Option<&str>,
+        compilers: &[crate::toolchain::Compiler],
+    ) -> Error {
+        let available: Vec<String> = compilers
+            .iter()
+            .filter(|c| c.language.eq_ignore_ascii_case(language))
+            .map(|c| {
+                format!(
+                    "{} {} for {} ({})",
+                    c.language,
+                    c.version,
+                    c.target,
+                    c.runtimes.join(", ")
+                )
+            })
+            .collect();
+        Error::NoCompiler {
+            language: String::from(language),
+            target: String::from(target.unwrap_or("native")),
+            runtime: String::from(runtime.unwrap_or("default")),
+            available: if available.is_empty() {
+                String::from("none")
+            } else {
+                available.join("; ")
+            },
+        }
+    }
+
+    pub fn not_reported(backend: &str, what: &str, source: Error) -> Error {
+        Error::NotReported {
+            backend: String::from(backend),
+            what: String::from(what),
+            source: Box::new(source),
+        }
+    }
+
+    pub fn no_library(file: &Path) -> Error {
+        Error::NoLibrary {
+            file: file.display().to_string(),
+        }
+    }
+
+    pub fn no_build_env(name: &str) -> Error {
+        Error::NoBuildEnv {
+            name: String::from(name),
+        }
+    }
+
+    pub fn declared_in_case(file: &Path, name: &str) -> Error {
+        Error::DeclaredInCase {
+            file: file.display().to_string(),
+            name: String::from(name),
+        }
+    }
+
+    pub fn syntax(file: &Path, line: usize, column: usize, message: &str) -> Error {
+        Error::Syntax {
+            file: file.display().to_string(),
+            line,
+            column,
+            message: String::from(message),
+        }
+    }
+
+    pub fn invalid_path(path: &Path, message: &str) -> Error {
+        Error::InvalidPath {
+            path: path.display().to_string(),
+            message: String::from(message),
+        }
+    }
+
+    pub fn binding(file: &Path, line: usize, message: &str) -> Error {
+        Error::Binding {
+            file: file.display().to_string(),
+            line,
+            message: String::from(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_status() {
+        assert!(Error::from_status(0, "", "").is_none());
+        let error = Error::from_status(
+            2,
+            "GPR2.Project_Error",
+            "/prj/lib.gpr:3:17: error: undefined attribute \"Foo\"",
+        )
+        .unwrap();
+        assert!(matches!(error, Error::AttributeNotFound(_)));
+        let exception = error.exception().unwrap();
+        assert_eq!(exception.status, 2);
+        assert_eq!(exception.name, "GPR2.Project_Error");
+        assert_eq!(
+            exception.location,
+            Some(Location {
+                file: String::from("/prj/lib.gpr"),
+                line: 3,
+                column: Some(17),
+            })
+        );
+        assert!(matches!(
+            Error::from_status(
+                2,
+                "GPR2.Project_Error",
+                "main.gpr:1:06: imported project file \"common.gpr\" not found"
+            ),
+            Some(Error::ProjectNotFound(_))
+        ));
+        assert!(matches!(
+            Error::from_status(2, "GPR2.Project_Error", "main.gpr:7: wrong syntax"),
+            Some(Error::InvalidProject(_))
+        ));
+        assert!(matches!(
+            Error::from_status(
+                2,
+                "GPR2.Project_Error",
+                "/home/u/runtime-tools/a.gpr:1:1: imported project file \"x.gpr\" not found"
+            ),
+            Some(Error::ProjectNotFound(_))
+        ));
+        assert!(matches!(
+            Error::from_status(2, "GPR2.Attribute_Error", "/prj/views/a.gpr: no such index"),
+            Some(Error::AttributeNotFound(_))
+        ));
+        assert!(matches!(
+            Error::from_status(2, "Ada.IO_Exceptions.Name_Error", "runtime/a.gpr"),
+            Some(Error::ProjectNotFound(_))
+        ));
+        assert!(matches!(
+            Error::from_status(3, "GPR2.Project_Error", "invalid runtime \"rts-foo\""),
+            Some(Error::InvalidRuntime(_))
+        ));
+        assert!(matches!(
+            Error::from_status(
+                2,
+                "GPR2.Project_Error",
+                "/prj/lib.gpr:4:7: undefined attribute \"Runtime\""
+            ),
+            Some(Error::AttributeNotFound(_))
+        ));
+        assert!(matches!(
+            Error::from_status(1, "GPR2C.Invalid_Request", "missing filename"),
+            Some(Error::InvalidRequest(_))
+        ));
+        let error =
+            Error::from_status(42, "Constraint_Error", "C:\\prj\\a.adb: index check").unwrap();
+        assert!(matches!(&error, Error::Exception(e) if e.status == 42 && e.location.is_none()));
+        assert_eq!(
+            error.to_string(),
+            "libgpr2: Constraint_Error (status 42): C:\\prj\\a.adb: index check"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let file = Path::new(OsStr::from_bytes(b"/prj/\xff.gpr"));
+        let error = Error::invalid_attribute(file, "Library_Kind", "both");
+        assert!(
+            matches!(&error, Error::InvalidAttribute { file, .. } if file == "/prj/\u{fffd}.gpr")
+        );
+        let value = crate::binding::AttributeValue::Single(String::from("both"));
+        assert!(Error::invalid_attribute_value(file, "Library_Kind", &value)
+            .to_string()
+            .contains("Library_Kind"));
+    }
 }
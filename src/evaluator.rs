@@ -0,0 +1,1040 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    ast::{Expr, Index, Item, ItemKind, Name, Qualifier, Span, Term},
+    backend::{self, attribute_key, AttributeKey, Attributes, Origin, Origins, Snapshot},
+    binding::{AttributeValue, Dirs, View},
+    error, parser, scenario,
+};
+
+/// Attributes of the gpr2 registry whose values are lists.
+const LIST_ATTRIBUTES: &[&str] = &[
+    "archive_builder",
+    "archive_builder_append_option",
+    "archive_indexer",
+    "artifacts",
+    "artifacts_in_exec_dir",
+    "artifacts_in_object_dir",
+    "config_file_switches",
+    "default_switches",
+    "dependency_driver",
+    "dependency_switches",
+    "excluded_source_dirs",
+    "excluded_source_files",
+    "global_compilation_switches",
+    "ignore_source_sub_dirs",
+    "implementation_exceptions",
+    "include_switches",
+    "inherit_source_path",
+    "interfaces",
+    "languages",
+    "leading_required_switches",
+    "leading_switches",
+    "library_encapsulated_options",
+    "library_interface",
+    "library_options",
+    "library_partial_linker",
+    "library_rpath_options",
+    "library_version_switches",
+    "linker_options",
+    "locally_removed_files",
+    "main",
+    "mapping_file_switches",
+    "multi_unit_switches",
+    "object_artifact_extensions",
+    "object_file_switches",
+    "object_lister",
+    "pic_option",
+    "project_files",
+    "project_path",
+    "required_artifacts",
+    "required_switches",
+    "response_file_switches",
+    "roots",
+    "run_path_option",
+    "runtime_source_dirs",
+    "shared_library_minimum_switches",
+    "source_artifact_extensions",
+    "source_dirs",
+    "source_file_switches",
+    "source_files",
+    "specification_exceptions",
+    "switches",
+    "trailing_required_switches",
+    "trailing_switches",
+];
+
+/// Attributes of the gpr2 registry that take a single value. Attributes
+/// that are in neither table take the kind of the expression they are
+/// declared with.
+const SINGLE_ATTRIBUTES: &[&str] = &[
+    "archive_suffix",
+    "body",
+    "body_suffix",
+    "canonical_target",
+    "casing",
+    "default_language",
+    "dot_replacement",
+    "driver",
+    "exec_dir",
+    "executable",
+    "executable_suffix",
+    "externally_built",
+    "implementation",
+    "implementation_suffix",
+    "language_kind",
+    "library_ali_dir",
+    "library_auto_init",
+    "library_dir",
+    "library_gcc",
+    "library_kind",
+    "library_name",
+    "library_reference_symbol_file",
+    "library_src_dir",
+    "library_standalone",
+    "library_symbol_file",
+    "library_symbol_policy",
+    "library_version",
+    "name",
+    "object_dir",
+    "object_file_suffix",
+    "runtime",
+    "runtime_dir",
+    "separate_suffix",
+    "shared_library_prefix",
+    "shared_library_suffix",
+    "spec",
+    "spec_suffix",
+    "specification",
+    "specification_suffix",
+    "target",
+    "toolchain_version",
+];
+
+/// Attributes that are not copied from an extended project.
+const NOT_INHERITED: &[&str] = &["name", "project_files"];
+
+/// Value of an expression together with the directory of the project each
+/// element comes from, which relative paths in the element refer to.
+#[derive(Debug, Clone)]
+struct Value {
+    value: AttributeValue,
+    dirs: Vec<PathBuf>,
+}
+
+impl Value {
+    fn single(value: String, dir: &Path) -> Value {
+        Value {
+            value: AttributeValue::Single(value),
+            dirs: vec![dir.to_path_buf()],
+        }
+    }
+
+    fn list(values: Vec<String>, dir: &Path) -> Value {
+        Value {
+            dirs: vec![dir.to_path_buf(); values.len()],
+            value: AttributeValue::List(values),
+        }
+    }
+}
+
+/// Project evaluated by the native backend.
+#[derive(Debug)]
+struct Project {
+    path: PathBuf,
+    name: String,
+    qualifier: Option<Qualifier>,
+    imports: Vec<PathBuf>,
+    extended: Option<PathBuf>,
+    extends_all: bool,
+    types: HashMap<String, Vec<String>>,
+    variables: HashMap<(Option<String>, String), Value>,
+    packages: Vec<String>,
+    attributes: Attributes,
+    origins: Origins,
+}
+
+impl Project {
+    fn declares(&self, name: &str) -> bool {
+        self.attributes
+            .keys()
+            .any(|(p, n, _)| p.is_none() && n == name)
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.qualifier {
+            Some(Qualifier::Abstract) => "k_abstract",
+            Some(Qualifier::Aggregate) => "k_aggregate",
+            Some(Qualifier::AggregateLibrary) => "k_aggregate_library",
+            Some(Qualifier::Configuration) => "k_configuration",
+            Some(Qualifier::Library) => "k_library",
+            _ if self.declares("library_name") && self.declares("library_dir") => "k_library",
+            _ => "k_standard",
+        }
+    }
+
+    fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+}
+
+fn is_list(attribute: &str) -> bool {
+    LIST_ATTRIBUTES.contains(&attribute)
+}
+
+fn is_single(attribute: &str) -> bool {
+    SINGLE_ATTRIBUTES.contains(&attribute)
+}
+
+fn key(package: Option<&str>, attribute: &str, index: &Option<Index>) -> AttributeKey {
+    let index = index.as_ref().map(|index| match index {
+        Index::Value(literal) => literal.value.as_str(),
+        Index::Others => "others",
+    });
+    attribute_key(package, attribute, index)
+}
+
+fn lower(name: &Name) -> Vec<String> {
+    name.parts.iter().map(|p| p.to_lowercase()).collect()
+}
+
+/// Evaluates a project tree without libgpr2.
+struct Evaluator {
+    context: BTreeMap<String, String>,
+    projects: HashMap<PathBuf, Project>,
+    order: Vec<PathBuf>,
+    loading: Vec<PathBuf>,
+    referenced: HashMap<String, String>,
+}
+
+type Result<T> = std::result::Result<T, error::Error>;
+
+/// Error of the evaluation at `span` of `file`, named like the one libgpr2
+/// reports.
+fn exception(file: &Path, span: Span, message: &str) -> error::Exception {
+    error::Exception::new(
+        2,
+        "GPR2.Project_Error",
+        &format!(
+            "{}:{}:{}: {message}",
+            file.display(),
+            span.line,
+            span.column
+        ),
+    )
+}
+
+impl Evaluator {
+    fn error<T>(&self, file: &Path, span: Span, message: &str) -> Result<T> {
+        Err(error::Error::InvalidProject(exception(file, span, message)))
+    }
+
+    /// Loads the project file `path` and all projects it depends on.
+    fn load(&mut self, path: &Path) -> Result<PathBuf> {
+        let path = path.canonicalize()?;
+        if self.projects.contains_key(&path) || self.loading.contains(&path) {
+            return Ok(path);
+        }
+        let source = std::fs::read_to_string(&path)?;
+        let ast = parser::parse(&source, &path)?;
+        self.loading.push(path.clone());
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let resolve = |literal: &crate::ast::Literal| {
+            scenario::resolve_project_path(&dir, &literal.value).map_or_else(
+                || {
+                    Err(error::Error::ProjectNotFound(exception(
+                        &path,
+                        literal.span,
+                        &format!("imported project file \"{}\" not found", literal.value),
+                    )))
+                },
+                Ok,
+            )
+        };
+        let mut imports = Vec::new();
+        for with in &ast.withs {
+            for literal in &with.paths {
+                let file = resolve(literal)?;
+                imports.push(self.load(&file)?);
+            }
+        }
+        let extended = match &ast.extends {
+            Some(extends) => {
+                let file = resolve(&extends.path)?;
+                Some(self.load(&file)?)
+            }
+            None => None,
+        };
+        let mut project = Project {
+            path: path.clone(),
+            name: ast.name.to_string(),
+            qualifier: ast.qualifier,
+            imports,
+            extended,
+            extends_all: ast.extends.as_ref().is_some_and(|e| e.all),
+            types: HashMap::new(),
+            variables: HashMap::new(),
+            packages: Vec::new(),
+            attributes: Attributes::new(),
+            origins: Origins::new(),
+        };
+        if let Some(extended) = project.extended.as_ref().and_then(|e| self.projects.get(e)) {
+            let inherited = |(_, name, _): &AttributeKey| !NOT_INHERITED.contains(&name.as_str());
+            project.attributes = extended
+                .attributes
+                .iter()
+                .filter(|(key, _)| inherited(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            project.origins = extended
+                .origins
+                .iter()
+                .filter(|(key, _)| inherited(key))
+                .map(|(key, origin)| (key.clone(), origin.clone()))
+                .collect();
+            project.packages = extended.packages.clone();
+        }
+        self.items(&mut project, None, &ast.items)?;
+        self.loading.pop();
+        self.projects.insert(path.clone(), project);
+        self.order.push(path.clone());
+        Ok(path)
+    }
+
+    fn items(
+        &mut self,
+        project: &mut Project,
+        package: Option<&str>,
+        items: &[Item],
+    ) -> Result<()> {
+        for item in items {
+            self.item(project, package, item)?;
+        }
+        Ok(())
+    }
+
+    fn item(&mut self, project: &mut Project, package: Option<&str>, item: &Item) -> Result<()> {
+        let package_key = package.map(str::to_lowercase);
+        match &item.kind {
+            ItemKind::Attribute { name, index, value } => {
+                let attribute = name.last().to_lowercase();
+                let Value { value, dirs } = self.expr(project, package, value)?;
+                match &value {
+                    AttributeValue::Single(_) if is_list(&attribute) => {
+                        return self.error(
+                            &project.path,
+                            item.span,
+                            &format!("attribute {name} expects a list"),
+                        )
+                    }
+                    AttributeValue::List(_) if is_single(&attribute) => {
+                        return self.error(
+                            &project.path,
+                            item.span,
+                            &format!("attribute {name} expects a single value"),
+                        )
+                    }
+                    _ => {}
+                }
+                let key = key(package, &attribute, index);
+                project.origins.insert(
+                    key.clone(),
+                    Origin {
+                        project: project.path.clone(),
+                        dirs,
+                    },
+                );
+                project.attributes.insert(key, value);
+            }
+            ItemKind::Type { name, values } => {
+                project.types.insert(
+                    name.last().to_lowercase(),
+                    values.iter().map(|v| v.value.clone()).collect(),
+                );
+            }
+            ItemKind::Variable {
+                name,
+                type_name,
+                value,
+            } => {
+                let value = self.expr(project, package, value)?;
+                if let Some(type_name) = type_name {
+                    let values = self.find_type(project, type_name)?;
+                    match &value.value {
+                        AttributeValue::Single(v) if values.contains(v) => {}
+                        _ => {
+                            return self.error(
+                                &project.path,
+                                item.span,
+                                &format!(
+                                    "value {} of {name} is not one of {}",
+                                    describe(&value.value),
+                                    values.join(", ")
+                                ),
+                            )
+                        }
+                    }
+                }
+                project
+                    .variables
+                    .insert((package_key, name.last().to_lowercase()), value);
+            }
+            ItemKind::Package {
+                name,
+                extends,
+                renames,
+                items,
+            } => {
+                let package_name = name.last().to_lowercase();
+                if let Some(base) = extends.as_ref().or(renames.as_ref()) {
+                    self.copy_package(project, &package_name, base)?;
+                }
+                if !project.packages.contains(&package_name) {
+                    project.packages.push(package_name.clone());
+                }
+                self.items(project, Some(&package_name), items)?;
+            }
+            ItemKind::Case {
+                variable,
+                alternatives,
+            } => {
+                let value = match self.variable(project, package, variable)?.value {
+                    AttributeValue::Single(value) => value,
+                    AttributeValue::List(_) => {
+                        return self.error(
+                            &project.path,
+                            variable.span,
+                            &format!("case variable {variable} must not be a list"),
+                        )
+                    }
+                };
+                if let Some(alternative) = alternatives
+                    .iter()
+                    .find(|a| a.choices.is_empty() || a.choices.iter().any(|c| c.value == value))
+                {
+                    self.items(project, package, &alternative.items)?;
+                }
+            }
+            ItemKind::Null => {}
+        }
+        Ok(())
+    }
+
+    /// Copies the attributes of the package `base` (`Project.Package`) into
+    /// `package` of `project`.
+    fn copy_package(&self, project: &mut Project, package: &str, base: &Name) -> Result<()> {
+        let parts = lower(base);
+        let [prefix @ .., base_package] = parts.as_slice() else {
+            return Ok(());
+        };
+        let Some(source) = self.find_project(project, &prefix.join(".")) else {
+            return self.error(
+                &project.path,
+                base.span,
+                &format!("unknown project in {base}"),
+            );
+        };
+        let copied: Vec<_> = source
+            .attributes
+            .iter()
+            .filter(|((p, _, _), _)| p.as_deref() == Some(base_package))
+            .map(|(key @ (_, n, i), v)| {
+                (
+                    (Some(package.to_owned()), n.clone(), i.clone()),
+                    v.clone(),
+                    source.origins.get(key).cloned(),
+                )
+            })
+            .collect();
+        for (key, value, origin) in copied {
+            if let Some(origin) = origin {
+                project.origins.insert(key.clone(), origin);
+            }
+            project.attributes.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// Returns the project named `name` that is visible from `project`.
+    fn find_project<'a>(&'a self, project: &'a Project, name: &str) -> Option<&'a Project> {
+        if name.eq_ignore_ascii_case(&project.name) || name.eq_ignore_ascii_case("project") {
+            return Some(project);
+        }
+        project
+            .imports
+            .iter()
+            .chain(project.extended.iter())
+            .filter_map(|path| self.projects.get(path))
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    fn find_type(&self, project: &Project, name: &Name) -> Result<Vec<String>> {
+        let parts = lower(name);
+        let (scope, type_name) = match parts.as_slice() {
+            [type_name] => (Some(project), type_name),
+            [prefix, type_name] => (self.find_project(project, prefix), type_name),
+            _ => (None, &parts[0]),
+        };
+        let extended = scope
+            .and_then(|s| s.extended.as_ref())
+            .and_then(|e| self.projects.get(e));
+        scope
+            .and_then(|s| s.types.get(type_name))
+            .or_else(|| extended.and_then(|e| e.types.get(type_name)))
+            .cloned()
+            .map_or_else(
+                || self.error(&project.path, name.span, &format!("unknown type {name}")),
+                Ok,
+            )
+    }
+
+    fn variable(&self, project: &Project, package: Option<&str>, name: &Name) -> Result<Value> {
+        let parts = lower(name);
+        let package = package.map(str::to_lowercase);
+        let found = match parts.as_slice() {
+            [variable] => project
+                .variables
+                .get(&(package.clone(), variable.clone()))
+                .or_else(|| project.variables.get(&(None, variable.clone()))),
+            [prefix, variable] => {
+                if project.packages.contains(prefix) {
+                    project
+                        .variables
+                        .get(&(Some(prefix.clone()), variable.clone()))
+                } else {
+                    self.find_project(project, prefix)
+                        .and_then(|p| p.variables.get(&(None, variable.clone())))
+                }
+            }
+            [prefix, package, variable] => self
+                .find_project(project, prefix)
+                .and_then(|p| p.variables.get(&(Some(package.clone()), variable.clone()))),
+            _ => None,
+        };
+        found.cloned().map_or_else(
+            || {
+                self.error(
+                    &project.path,
+                    name.span,
+                    &format!("unknown variable {name}"),
+                )
+            },
+            Ok,
+        )
+    }
+
+    fn attribute(
+        &self,
+        project: &Project,
+        prefix: &Name,
+        name: &Name,
+        index: &Option<Index>,
+    ) -> Result<Value> {
+        let parts = lower(prefix);
+        let (scope, package) = match parts.as_slice() {
+            [single] if project.packages.contains(single) => (Some(project), Some(single.clone())),
+            [single] => (self.find_project(project, single), None),
+            [prefix, package] => (self.find_project(project, prefix), Some(package.clone())),
+            _ => (None, None),
+        };
+        let Some(scope) = scope else {
+            return self.error(
+                &project.path,
+                prefix.span,
+                &format!("unknown project or package {prefix}"),
+            );
+        };
+        let attribute = name.last().to_lowercase();
+        let key = key(package.as_deref(), &attribute, index);
+        if let Some(value) = scope.attributes.get(&key) {
+            return Ok(Value {
+                value: value.clone(),
+                dirs: scope
+                    .origins
+                    .get(&key)
+                    .map_or_else(Vec::new, |origin| origin.dirs.clone()),
+            });
+        }
+        Ok(
+            match package
+                .is_none()
+                .then(|| backend::default_value(&scope.name, &attribute))
+                .flatten()
+            {
+                Some(AttributeValue::Single(value)) => Value::single(value, scope.dir()),
+                Some(AttributeValue::List(values)) => Value::list(values, scope.dir()),
+                None if is_list(&attribute) => Value::list(Vec::new(), scope.dir()),
+                None => Value::single(String::new(), scope.dir()),
+            },
+        )
+    }
+
+    /// Value of the external `name`, set either in the context or in the
+    /// environment.
+    fn external(&mut self, name: &str) -> Option<String> {
+        let value = self
+            .context
+            .get(name)
+            .or_else(|| {
+                self.context
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v)
+            })
+            .cloned()
+            .or_else(|| env::var(name).ok())?;
+        self.referenced.insert(name.to_owned(), value.clone());
+        Some(value)
+    }
+
+    fn single(&mut self, project: &Project, package: Option<&str>, expr: &Expr) -> Result<String> {
+        match self.expr(project, package, expr)?.value {
+            AttributeValue::Single(value) => Ok(value),
+            AttributeValue::List(_) => {
+                self.error(&project.path, expr.span, "expected a single value")
+            }
+        }
+    }
+
+    fn call(
+        &mut self,
+        project: &Project,
+        package: Option<&str>,
+        function: &Name,
+        arguments: &[Expr],
+    ) -> Result<Value> {
+        let mut values = Vec::new();
+        for argument in arguments {
+            values.push(self.single(project, package, argument)?);
+        }
+        let function_name = function.to_string().to_lowercase();
+        match (function_name.as_str(), values.as_slice()) {
+            ("external", [name, default @ ..]) if default.len() <= 1 => {
+                match self.external(name).or_else(|| default.first().cloned()) {
+                    Some(value) => Ok(Value::single(value, project.dir())),
+                    None => self.error(
+                        &project.path,
+                        function.span,
+                        &format!("undefined external reference \"{name}\""),
+                    ),
+                }
+            }
+            ("external_as_list", [name, separator]) => Ok(Value::list(
+                self.external(name)
+                    .map(|value| split(&value, separator))
+                    .unwrap_or_default(),
+                project.dir(),
+            )),
+            ("split", [value, separator]) => {
+                Ok(Value::list(split(value, separator), project.dir()))
+            }
+            _ => self.error(
+                &project.path,
+                function.span,
+                &format!("invalid call of {function} with {} arguments", values.len()),
+            ),
+        }
+    }
+
+    fn term(&mut self, project: &Project, package: Option<&str>, term: &Term) -> Result<Value> {
+        match term {
+            Term::String(literal) => Ok(Value::single(literal.value.clone(), project.dir())),
+            Term::List(exprs, _) => {
+                let mut values = Vec::new();
+                let mut dirs = Vec::new();
+                for expr in exprs {
+                    let value = self.expr(project, package, expr)?;
+                    match value.value {
+                        AttributeValue::Single(value) => values.push(value),
+                        AttributeValue::List(list) => values.extend(list),
+                    }
+                    dirs.extend(value.dirs);
+                }
+                Ok(Value {
+                    value: AttributeValue::List(values),
+                    dirs,
+                })
+            }
+            Term::Variable(name) => self.variable(project, package, name),
+            Term::Attribute {
+                prefix,
+                name,
+                index,
+            } => self.attribute(project, prefix, name, index),
+            Term::Call {
+                function,
+                arguments,
+            } => self.call(project, package, function, arguments),
+        }
+    }
+
+    fn expr(&mut self, project: &Project, package: Option<&str>, expr: &Expr) -> Result<Value> {
+        let mut result: Option<Value> = None;
+        for term in &expr.terms {
+            let Value { value, dirs } = self.term(project, package, term)?;
+            result = Some(match (result, value) {
+                (None, value) => Value { value, dirs },
+                // A concatenated string refers to the directory of its
+                // first part.
+                (
+                    Some(Value {
+                        value: AttributeValue::Single(a),
+                        dirs: first,
+                    }),
+                    AttributeValue::Single(b),
+                ) => Value {
+                    value: AttributeValue::Single(a + &b),
+                    dirs: first,
+                },
+                (
+                    Some(Value {
+                        value: AttributeValue::List(mut a),
+                        dirs: mut a_dirs,
+                    }),
+                    AttributeValue::Single(b),
+                ) => {
+                    a.push(b);
+                    a_dirs.extend(dirs);
+                    Value {
+                        value: AttributeValue::List(a),
+                        dirs: a_dirs,
+                    }
+                }
+                (
+                    Some(Value {
+                        value: AttributeValue::List(mut a),
+                        dirs: mut a_dirs,
+                    }),
+                    AttributeValue::List(b),
+                ) => {
+                    a.extend(b);
+                    a_dirs.extend(dirs);
+                    Value {
+                        value: AttributeValue::List(a),
+                        dirs: a_dirs,
+                    }
+                }
+                (
+                    Some(Value {
+                        value: AttributeValue::Single(_),
+                        ..
+                    }),
+                    AttributeValue::List(_),
+                ) => {
+                    return self.error(
+                        &project.path,
+                        term.span(),
+                        "a list cannot be appended to a string",
+                    )
+                }
+            });
+        }
+        Ok(result.unwrap_or_else(|| Value::single(String::new(), project.dir())))
+    }
+}
+
+fn split(value: &str, separator: &str) -> Vec<String> {
+    if separator.is_empty() {
+        return vec![value.to_owned()];
+    }
+    value
+        .split(separator)
+        .filter(|v| !v.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn describe(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::Single(value) => format!("\"{value}\""),
+        AttributeValue::List(values) => format!("({})", values.join(", ")),
+    }
+}
+
+/// Loads the project tree of `file` with the native evaluator.
+pub fn load(
+    file: &Path,
+    context: &BTreeMap<String, String>,
+    config: Option<&Path>,
+    src_subdirs: Option<&str>,
+) -> Result<Snapshot> {
+    let mut evaluator = Evaluator {
+        context: context.clone(),
+        projects: HashMap::new(),
+        order: Vec::new(),
+        loading: Vec::new(),
+        referenced: HashMap::new(),
+    };
+    if !file.is_file() {
+        return Err(error::Error::ProjectNotFound(error::Exception::new(
+            2,
+            "GPR2.Project_Error",
+            &format!("project file \"{}\" not found", file.display()),
+        )));
+    }
+    let root = evaluator.load(file)?;
+    let aggregated = {
+        let project = &evaluator.projects[&root];
+        let externals: Vec<(String, String)> = project
+            .attributes
+            .iter()
+            .filter_map(|((p, n, i), v)| match (p, n.as_str(), i, v) {
+                (None, "external", Some(name), AttributeValue::Single(value)) => {
+                    Some((name.clone(), value.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        let files = match project
+            .attributes
+            .get(&(None, String::from("project_files"), None))
+        {
+            Some(AttributeValue::List(files)) if project.kind().starts_with("k_aggregate") => {
+                files.iter().map(|f| project.dir().join(f)).collect()
+            }
+            _ => Vec::new(),
+        };
+        (externals, files)
+    };
+    let (externals, files) = aggregated;
+    for (name, value) in externals {
+        if !evaluator
+            .context
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case(&name))
+        {
+            evaluator.context.insert(name, value);
+        }
+    }
+    for file in files {
+        evaluator.load(&file)?;
+    }
+    let config = match config {
+        Some(config) => Some(evaluator.load(config)?),
+        None => None,
+    };
+    let mut snapshot = Snapshot {
+        root_view: root.display().to_string(),
+        src_subdirs: src_subdirs.map(String::from),
+        context: evaluator.referenced.clone(),
+        ..Snapshot::default()
+    };
+    let target = |path: &PathBuf| match evaluator.projects[path].attributes.get(&(
+        None,
+        String::from("target"),
+        None,
+    )) {
+        Some(AttributeValue::Single(target)) => Some(target.clone()),
+        _ => None,
+    };
+    snapshot.target = target(&root)
+        .or_else(|| config.as_ref().and_then(target))
+        .unwrap_or_else(|| String::from("native"));
+    let order = std::iter::once(root.clone())
+        .chain(evaluator.order.iter().filter(|p| **p != root).cloned())
+        .collect::<Vec<_>>();
+    for path in &order {
+        let mut project = evaluator.projects.remove(path).unwrap();
+        if Some(path) == config.as_ref() {
+            project.qualifier = Some(Qualifier::Configuration);
+        }
+        let id = path.display().to_string();
+        let kind = String::from(project.kind());
+        let dir = project.dir().to_path_buf();
+        snapshot.origins.insert(id.clone(), project.origins);
+        snapshot.add_view(
+            View {
+                id: id.clone(),
+                name: project.name,
+                path: path.clone(),
+                dir,
+                kind,
+                extended: project.extended,
+                extends_all: project.extends_all,
+                dirs: Dirs::default(),
+            },
+            project.attributes,
+        );
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(
+        snapshot: &Snapshot,
+        pkg: Option<&str>,
+        name: &str,
+        index: Option<&str>,
+    ) -> Vec<String> {
+        match snapshot
+            .get_view_attribute(&snapshot.root_view, pkg, name, index)
+            .unwrap()
+            .value
+        {
+            AttributeValue::Single(value) => vec![value],
+            AttributeValue::List(values) => values,
+        }
+    }
+
+    #[test]
+    fn test_scenario() {
+        let file = Path::new("testdata/scenario.gpr");
+        let context = BTreeMap::from([
+            (String::from("ENABLE_TLS"), String::from("yes")),
+            (String::from("SCENARIO_MODE"), String::from("release")),
+            (String::from("SCENARIO_CFLAGS"), String::from("-O2  -g")),
+        ]);
+        let snapshot = load(file, &context, None, None).unwrap();
+        assert_eq!(
+            value(&snapshot, None, "library_name", None),
+            vec!["scenario_tls"]
+        );
+        assert_eq!(
+            value(&snapshot, None, "object_dir", None),
+            vec!["obj/release"]
+        );
+        assert_eq!(
+            value(&snapshot, Some("Compiler"), "Default_Switches", Some("Ada")),
+            vec!["-O2", "-g"]
+        );
+        assert_eq!(value(&snapshot, None, "name", None), vec!["Scenario"]);
+        assert_eq!(snapshot.views.len(), 2);
+        assert_eq!(
+            snapshot.view(&snapshot.root_view).unwrap().kind,
+            "k_library"
+        );
+        assert_eq!(snapshot.context["SCENARIO_MODE"], "release");
+        let context = BTreeMap::from([(String::from("SCENARIO_MODE"), String::from("fast"))]);
+        let error = load(file, &context, None, None).unwrap_err();
+        assert!(
+            error.to_string().contains("is not one of debug, release"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_extension() {
+        let snapshot = load(
+            Path::new("testdata/extending.gpr"),
+            &BTreeMap::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            value(&snapshot, None, "object_dir", None),
+            vec!["obj/plain"]
+        );
+        assert_eq!(
+            value(&snapshot, None, "source_dirs", None),
+            vec!["extending/src"]
+        );
+        assert_eq!(value(&snapshot, None, "name", None), vec!["Extending"]);
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let snapshot = load(
+            Path::new("testdata/aggregate.gpr"),
+            &BTreeMap::new(),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(snapshot.views.len(), 3);
+        assert_eq!(
+            snapshot.view(&snapshot.root_view).unwrap().kind,
+            "k_aggregate"
+        );
+        assert_eq!(
+            value(&snapshot, None, "external", Some("AGGREGATE_MODE")),
+            vec!["fast"]
+        );
+        assert_eq!(
+            snapshot
+                .attribute_indexes(&snapshot.root_view, None, "External")
+                .unwrap(),
+            vec!["AGGREGATE_MODE"]
+        );
+    }
+
+    #[test]
+    fn test_configuration() {
+        let config = Path::new("testdata/config/auto.cgpr");
+        let snapshot = load(
+            Path::new("testdata/plain.gpr"),
+            &BTreeMap::new(),
+            Some(config),
+            None,
+        )
+        .unwrap();
+        assert_eq!(snapshot.target, "x86_64-linux");
+        let id = config.canonicalize().unwrap().display().to_string();
+        let value = |pkg, name, index| match snapshot
+            .get_view_attribute(&id, pkg, name, index)
+            .unwrap()
+            .value
+        {
+            AttributeValue::Single(value) => vec![value],
+            AttributeValue::List(values) => values,
+        };
+        assert_eq!(value(None, "Run_Path_Option", None), vec!["-Wl,-rpath,"]);
+        assert_eq!(
+            value(None, "Library_Partial_Linker", None),
+            vec!["gcc", "-nostdlib", "-Wl,-r", "-o"]
+        );
+        assert_eq!(
+            value(Some("Compiler"), "Leading_Required_Switches", Some("Ada")),
+            vec!["-c", "-x", "ada", "-gnatA"]
+        );
+        assert_eq!(
+            value(Some("Compiler"), "Object_Path_Switches", Some("Ada")),
+            vec!["-gnateO="]
+        );
+        assert_eq!(
+            value(Some("Naming"), "Implementation_Exceptions", Some("Asm")),
+            vec!["crt0.S"]
+        );
+        assert_eq!(
+            value(Some("Install"), "Artifacts", Some("share/doc")),
+            vec!["README"]
+        );
+        assert_eq!(
+            value(Some("Compiler"), "Driver", Some("Ada")),
+            vec!["/opt/gnat/bin/gcc"]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let error = load(
+            Path::new("testdata/invalid.gpr"),
+            &BTreeMap::new(),
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(error, error::Error::InvalidProject(_)), "{error}");
+        assert!(
+            error
+                .to_string()
+                .ends_with("invalid.gpr:3:4: attribute Runtime expects a single value"),
+            "{error}"
+        );
+        let error = load(
+            Path::new("testdata/missing.gpr"),
+            &BTreeMap::new(),
+            None,
+            None,
+        )
+        .unwrap_err();
+        assert!(matches!(error, error::Error::ProjectNotFound(_)), "{error}");
+    }
+}
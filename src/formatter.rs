@@ -0,0 +1,504 @@
+use std::{fmt, path::Path};
+
+use super::{
+    error,
+    lexer::{self, Token, TokenKind},
+    parser,
+};
+
+/// Width after which lists are wrapped to one element per line.
+const MAX_WIDTH: usize = 79;
+
+const INDENT: usize = 3;
+
+const RESERVED: &[&str] = &[
+    "abstract", "all", "case", "end", "extends", "for", "is", "limited", "null", "others",
+    "package", "project", "renames", "type", "use", "when", "with",
+];
+
+/// Lines of a project file that differ from its formatted version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub file: String,
+    /// First line of the difference in the original file.
+    pub line: usize,
+    pub original: Vec<String>,
+    pub formatted: Vec<String>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:", self.file, self.line)?;
+        for line in &self.original {
+            write!(f, "\n-{line}")?;
+        }
+        for line in &self.formatted {
+            write!(f, "\n+{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Case,
+    When,
+    Other,
+}
+
+struct Statement<'a> {
+    tokens: Vec<Token<'a>>,
+    comment: Option<&'a str>,
+    level: usize,
+}
+
+enum Line<'a> {
+    Statement(Statement<'a>),
+    Comment(&'a str, usize),
+    Blank,
+}
+
+/// Adds the pending comments, empty ones stand for blank lines.
+fn flush_comments<'a>(lines: &mut Vec<Line<'a>>, comments: &mut Vec<&'a str>, level: usize) {
+    for comment in comments.drain(..) {
+        match comment {
+            "" => lines.push(Line::Blank),
+            comment => lines.push(Line::Comment(comment, level)),
+        }
+    }
+}
+
+fn is_terminator(statement: &[Token], token: &Token) -> bool {
+    token.kind == TokenKind::Semicolon
+        || (token.is_keyword("is") && !statement[0].is_keyword("type"))
+        || (token.kind == TokenKind::Arrow && statement[0].is_keyword("when"))
+}
+
+/// Splits the source into statements, comments and blank lines and
+/// assigns each of them its indentation level.
+fn lines<'a>(tokens: &[Token<'a>]) -> Vec<Line<'a>> {
+    let mut lines = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut comments: Vec<&'a str> = Vec::new();
+    let mut current: Vec<Token<'a>> = Vec::new();
+    let mut newlines = 0;
+    let mut blank = false;
+    for token in tokens {
+        match token.kind {
+            TokenKind::Whitespace => {
+                newlines += token.text.matches('\n').count();
+                if current.is_empty() && newlines > 1 {
+                    blank = true;
+                }
+                continue;
+            }
+            TokenKind::Comment if current.is_empty() => match lines.last_mut() {
+                Some(Line::Statement(statement))
+                    if newlines == 0 && statement.comment.is_none() && comments.is_empty() =>
+                {
+                    statement.comment = Some(token.text.trim_end())
+                }
+                _ => {
+                    if blank && (!lines.is_empty() || !comments.is_empty()) {
+                        comments.push("");
+                    }
+                    comments.push(token.text.trim_end());
+                }
+            },
+            _ => {
+                if current.is_empty() && blank && (!lines.is_empty() || !comments.is_empty()) {
+                    comments.push("");
+                }
+                current.push(*token);
+                if token.kind != TokenKind::Comment && is_terminator(&current, token) {
+                    let first = &current[0];
+                    let inner = blocks.len();
+                    let level = if first.is_keyword("end") {
+                        if blocks.last() == Some(&Block::When) {
+                            blocks.pop();
+                        }
+                        blocks.pop();
+                        blocks.len()
+                    } else if first.is_keyword("when") {
+                        if blocks.last() == Some(&Block::When) {
+                            blocks.pop();
+                        }
+                        let level = blocks.len();
+                        blocks.push(Block::When);
+                        level
+                    } else {
+                        let level = blocks.len();
+                        if token.is_keyword("is") {
+                            blocks.push(if first.is_keyword("case") {
+                                Block::Case
+                            } else {
+                                Block::Other
+                            });
+                        }
+                        level
+                    };
+                    // Comments before an `end` belong to the closed block.
+                    flush_comments(
+                        &mut lines,
+                        &mut comments,
+                        if first.is_keyword("end") {
+                            inner
+                        } else {
+                            level
+                        },
+                    );
+                    lines.push(Line::Statement(Statement {
+                        tokens: std::mem::take(&mut current),
+                        comment: None,
+                        level,
+                    }));
+                }
+            }
+        }
+        newlines = 0;
+        blank = false;
+    }
+    flush_comments(&mut lines, &mut comments, 0);
+    lines
+}
+
+fn text(tokens: &[Token], i: usize) -> String {
+    let token = &tokens[i];
+    let reserved = RESERVED.iter().any(|keyword| token.is_keyword(keyword))
+        && tokens.get(i + 1).map(|t| t.kind) != Some(TokenKind::Apostrophe);
+    // Qualifiers like `library` are only keywords in the project header.
+    let qualifier = token.kind == TokenKind::Identifier
+        && tokens[i + 1..]
+            .iter()
+            .position(|t| t.is_keyword("project"))
+            .is_some_and(|p| tokens.get(i + p + 2).map(|t| t.kind) != Some(TokenKind::Apostrophe))
+        && tokens[..i].iter().all(|t| t.kind == TokenKind::Identifier);
+    if reserved || qualifier {
+        token.text.to_lowercase()
+    } else {
+        String::from(token.text)
+    }
+}
+
+fn space(previous: &Token, next: &Token) -> bool {
+    !matches!(
+        next.kind,
+        TokenKind::Comma
+            | TokenKind::Semicolon
+            | TokenKind::RightParen
+            | TokenKind::Dot
+            | TokenKind::Apostrophe
+    ) && !matches!(
+        previous.kind,
+        TokenKind::LeftParen | TokenKind::Dot | TokenKind::Apostrophe
+    )
+}
+
+/// Width of `line` in characters.
+fn width(line: &str) -> usize {
+    line.chars().count()
+}
+
+/// Renders a statement. `align` is the width up to which the part before
+/// `use` is padded, with `wrap` lists are split after each element.
+fn render(tokens: &[Token], indent: usize, align: usize, wrap: bool) -> Vec<String> {
+    let mut lines = vec![" ".repeat(indent)];
+    // Continuation columns of the open parentheses and whether they are
+    // lists rather than arguments or indexes.
+    let mut columns: Vec<(usize, bool)> = Vec::new();
+    let mut newline = false;
+    for (i, token) in tokens.iter().enumerate() {
+        let continuation = columns.last().map_or(indent + 2, |c| c.0);
+        let line = lines.last_mut().unwrap();
+        if token.kind == TokenKind::Comment {
+            line.push(' ');
+            line.push_str(token.text.trim_end());
+            newline = true;
+            continue;
+        }
+        if newline {
+            lines.push(" ".repeat(continuation));
+            newline = false;
+        } else if i > 0 && space(&tokens[i - 1], token) {
+            if token.is_keyword("use") {
+                while width(line) < indent + align {
+                    line.push(' ');
+                }
+            }
+            line.push(' ');
+        }
+        let line = lines.last_mut().unwrap();
+        line.push_str(&text(tokens, i));
+        match token.kind {
+            TokenKind::LeftParen => {
+                let list = i == 0
+                    || tokens[i - 1].is_keyword("use")
+                    || tokens[i - 1].is_keyword("is")
+                    || matches!(tokens[i - 1].kind, TokenKind::Assign | TokenKind::Ampersand);
+                columns.push((width(line), list));
+            }
+            TokenKind::RightParen => {
+                columns.pop();
+            }
+            TokenKind::Comma if wrap && columns.last().is_some_and(|c| c.1) => newline = true,
+            _ => (),
+        }
+    }
+    lines
+}
+
+/// Width of the part of an attribute declaration before `use` and whether
+/// the attribute is indexed.
+fn prefix_width(statement: &Statement) -> Option<(usize, bool)> {
+    if !statement.tokens[0].is_keyword("for") {
+        return None;
+    }
+    let end = statement.tokens.iter().position(|t| t.is_keyword("use"))?;
+    let prefix = &statement.tokens[..end];
+    if prefix.iter().any(|t| t.kind == TokenKind::Comment) {
+        return None;
+    }
+    Some((
+        width(&render(prefix, 0, 0, false)[0]),
+        prefix.iter().any(|t| t.kind == TokenKind::LeftParen),
+    ))
+}
+
+/// Formats the project file source `source`: packages and case statements
+/// are indented by three spaces, reserved words are lower case, `use` of
+/// consecutive attribute declarations is aligned and lists that do not fit
+/// into a line are wrapped. Comments and single blank lines are kept.
+pub fn format_source(source: &str, file: &Path) -> Result<String, error::Error> {
+    parser::parse(source, file)?;
+    let tokens = lexer::tokenize(source);
+    let lines = lines(&tokens);
+    // Runs of attribute declarations with or without index are aligned.
+    let mut alignment = vec![0; lines.len()];
+    let mut i = 0;
+    while i < lines.len() {
+        let first = match &lines[i] {
+            Line::Statement(statement) => prefix_width(statement).map(|p| (statement.level, p.1)),
+            _ => None,
+        };
+        let mut j = i;
+        let mut width = 0;
+        while let (Some((level, indexed)), Some(Line::Statement(statement))) = (first, lines.get(j))
+        {
+            match prefix_width(statement) {
+                Some((w, other)) if statement.level == level && other == indexed => {
+                    width = width.max(w)
+                }
+                _ => break,
+            }
+            j += 1;
+        }
+        if j > i + 1 {
+            alignment[i..j].fill(width);
+        }
+        i = j.max(i + 1);
+    }
+    let mut output = String::new();
+    for (line, align) in lines.iter().zip(alignment) {
+        match line {
+            Line::Statement(statement) => {
+                let indent = statement.level * INDENT;
+                let mut rendered = render(&statement.tokens, indent, align, false);
+                if rendered.iter().any(|l| width(l) > MAX_WIDTH) {
+                    rendered = render(&statement.tokens, indent, align, true);
+                }
+                output.push_str(&rendered.join("\n"));
+                if let Some(comment) = statement.comment {
+                    output.push(' ');
+                    output.push_str(comment);
+                }
+            }
+            Line::Comment(comment, level) => {
+                output.push_str(&" ".repeat(level * INDENT));
+                output.push_str(comment);
+            }
+            Line::Blank => (),
+        }
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Formats the project file `file` in place. Returns whether it changed.
+pub fn format_file(file: &Path) -> Result<bool, error::Error> {
+    let source = std::fs::read_to_string(file)?;
+    let formatted = format_source(&source, file)?;
+    if formatted == source {
+        return Ok(false);
+    }
+    std::fs::write(file, formatted)?;
+    Ok(true)
+}
+
+/// Compares the project file `file` with its formatted version without
+/// changing it. An empty result means that the file is formatted.
+pub fn check_formatting(file: &Path) -> Result<Vec<Difference>, error::Error> {
+    let source = std::fs::read_to_string(file)?;
+    let formatted = format_source(&source, file)?;
+    Ok(differences(
+        &file.display().to_string(),
+        &source.split('\n').collect::<Vec<_>>(),
+        &formatted.split('\n').collect::<Vec<_>>(),
+    ))
+}
+
+/// Line based diff from the longest common subsequence.
+fn differences(file: &str, original: &[&str], formatted: &[&str]) -> Vec<Difference> {
+    let (n, m) = (original.len(), formatted.len());
+    let mut common = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if original[i] == formatted[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut result: Vec<Difference> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut open = false;
+    while i < n || j < m {
+        if i < n && j < m && original[i] == formatted[j] {
+            i += 1;
+            j += 1;
+            open = false;
+            continue;
+        }
+        if !open {
+            result.push(Difference {
+                file: String::from(file),
+                line: i + 1,
+                original: Vec::new(),
+                formatted: Vec::new(),
+            });
+            open = true;
+        }
+        let difference = result.last_mut().unwrap();
+        if j < m && (i == n || common[i][j + 1] >= common[i + 1][j]) {
+            difference.formatted.push(String::from(formatted[j]));
+            j += 1;
+        } else {
+            difference.original.push(String::from(original[i]));
+            i += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        format_source(source, Path::new("test.gpr")).unwrap()
+    }
+
+    #[test]
+    fn test_format() {
+        let source = "\n\nWITH \"common\";\nLibrary Project Lib Is\n  --  Sources\n\n\n\
+                      for Source_Dirs use (\"src\");  --  main\nfor Library_Name Use \"lib\";\n\
+                      Mode : Common.Mode_Type := External(\"MODE\" , \"debug\");\n\
+                      Package Compiler is\nFor Switches(others) use Project'Source_Dirs & (\"-g\");\n\
+                      case Mode is when \"debug\" => null; when others =>\n\
+                      for Switches (\"Ada\") use (\"-O2\");\nend case;\n  -- done\nEnd Compiler;\n\
+                      end Lib;";
+        assert_eq!(
+            format(source),
+            "with \"common\";\n\
+             library project Lib is\n\
+             \x20  --  Sources\n\
+             \n\
+             \x20  for Source_Dirs  use (\"src\"); --  main\n\
+             \x20  for Library_Name use \"lib\";\n\
+             \x20  Mode : Common.Mode_Type := External (\"MODE\", \"debug\");\n\
+             \x20  package Compiler is\n\
+             \x20     for Switches (others) use Project'Source_Dirs & (\"-g\");\n\
+             \x20     case Mode is\n\
+             \x20        when \"debug\" =>\n\
+             \x20           null;\n\
+             \x20        when others =>\n\
+             \x20           for Switches (\"Ada\") use (\"-O2\");\n\
+             \x20     end case;\n\
+             \x20     -- done\n\
+             \x20  end Compiler;\n\
+             end Lib;\n"
+        );
+        assert_eq!(format(&format(source)), format(source));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let source = "project P is\n   for Source_Dirs use (\"first/source/directory\", \
+                      \"second/source/directory\", -- generated\n\"third\");\nend P;\n";
+        assert_eq!(
+            format(source),
+            "project P is\n   \
+             for Source_Dirs use (\"first/source/directory\",\n                        \
+             \"second/source/directory\", -- generated\n                        \
+             \"third\");\nend P;\n"
+        );
+        assert_eq!(format(&format(source)), format(source));
+    }
+
+    #[test]
+    fn test_unicode_width() {
+        let source = "project P is\nfor Exec_Dir use \"bin\";\nfor Object_Dir use \"obj\";\n\
+                      for Main (\"é\") use \"é\";\nfor Main (\"ab\") use \"b\";\nend P;\n";
+        assert_eq!(
+            format(source),
+            "project P is\n   \
+             for Exec_Dir   use \"bin\";\n   \
+             for Object_Dir use \"obj\";\n   \
+             for Main (\"é\")  use \"é\";\n   \
+             for Main (\"ab\") use \"b\";\nend P;\n"
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let file = std::env::temp_dir().join("gpr_rust_test_format.gpr");
+        std::fs::write(&file, "project P is\nfor Object_Dir use \"obj\";\nend P;\n").unwrap();
+        let result = check_formatting(&file).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].line, 2);
+        assert_eq!(result[0].original, vec!["for Object_Dir use \"obj\";"]);
+        assert_eq!(result[0].formatted, vec!["   for Object_Dir use \"obj\";"]);
+        assert!(format_file(&file).unwrap());
+        assert!(!format_file(&file).unwrap());
+        assert!(check_formatting(&file).unwrap().is_empty());
+        std::fs::write(&file, "project P is\nend Q").unwrap();
+        assert!(check_formatting(&file).is_err());
+    }
+
+    #[test]
+    fn test_testdata() {
+        let mut dirs = vec![std::path::PathBuf::from("testdata")];
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let file = entry.unwrap().path();
+                if file.is_dir() {
+                    dirs.push(file);
+                    continue;
+                }
+                if file.extension().is_none_or(|e| e != "gpr") || file.ends_with("invalid.gpr") {
+                    continue;
+                }
+                let differences = check_formatting(&file).unwrap();
+                assert!(
+                    differences.is_empty(),
+                    "{}",
+                    differences
+                        .iter()
+                        .map(Difference::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use std::{
+    fmt::{self, Write},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::error;
+
+/// Qualifier of a project declaration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Standard,
+    Library,
+    Abstract,
+    Aggregate,
+    AggregateLibrary,
+}
+
+impl ProjectKind {
+    /// Converts the kind of a view as reported by libgpr2, e.g.
+    /// `k_aggregate_library`.
+    pub(crate) fn from_view(file: &Path, kind: &str) -> Result<ProjectKind, error::Error> {
+        match kind.to_lowercase().as_str() {
+            "k_standard" => Ok(ProjectKind::Standard),
+            "k_library" => Ok(ProjectKind::Library),
+            "k_abstract" => Ok(ProjectKind::Abstract),
+            "k_aggregate" => Ok(ProjectKind::Aggregate),
+            "k_aggregate_library" => Ok(ProjectKind::AggregateLibrary),
+            kind => Err(error::Error::invalid_attribute(file, "kind", kind)),
+        }
+    }
+
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, ProjectKind::Aggregate | ProjectKind::AggregateLibrary)
+    }
+}
+
+impl fmt::Display for ProjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectKind::Standard => write!(f, "project"),
+            ProjectKind::Library => write!(f, "library project"),
+            ProjectKind::Abstract => write!(f, "abstract project"),
+            ProjectKind::Aggregate => write!(f, "aggregate project"),
+            ProjectKind::AggregateLibrary => write!(f, "aggregate library project"),
+        }
+    }
+}
+
+/// Right hand side of an attribute or variable declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    String(String),
+    List(Vec<Expression>),
+    /// Reference to a variable or attribute, e.g. `Mode` or
+    /// `Shared'Source_Dirs`.
+    Reference(String),
+    External {
+        name: String,
+        default: Option<String>,
+    },
+    ExternalAsList {
+        name: String,
+        separator: String,
+    },
+    /// Expressions joined with `&`.
+    Concat(Vec<Expression>),
+}
+
+impl From<&str> for Expression {
+    fn from(value: &str) -> Expression {
+        Expression::String(String::from(value))
+    }
+}
+
+impl From<String> for Expression {
+    fn from(value: String) -> Expression {
+        Expression::String(value)
+    }
+}
+
+impl From<&[&str]> for Expression {
+    fn from(values: &[&str]) -> Expression {
+        Expression::List(values.iter().map(|v| Expression::from(*v)).collect())
+    }
+}
+
+impl<const N: usize> From<[&str; N]> for Expression {
+    fn from(values: [&str; N]) -> Expression {
+        Expression::from(&values[..])
+    }
+}
+
+/// Quotes `value` as GPR string literal.
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Converts a Rust identifier such as `point_type` or `PointType` into an
+/// Ada identifier (`Point_Type`).
+pub(crate) fn ada_name(name: &str) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let mut words: Vec<String> = Vec::new();
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut word = String::new();
+        let mut previous_lower = false;
+        for c in part.chars() {
+            if c.is_uppercase() && previous_lower {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lower = c.is_lowercase() || c.is_ascii_digit();
+            word.push(c);
+        }
+        words.push(word);
+    }
+    words
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => {
+                    first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+                }
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::String(value) => write!(f, "{}", quote(value)),
+            Expression::List(values) => {
+                write!(f, "(")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, ")")
+            }
+            Expression::Reference(name) => write!(f, "{name}"),
+            Expression::External { name, default } => match default {
+                Some(default) => write!(f, "external ({}, {})", quote(name), quote(default)),
+                None => write!(f, "external ({})", quote(name)),
+            },
+            Expression::ExternalAsList { name, separator } => {
+                write!(
+                    f,
+                    "external_as_list ({}, {})",
+                    quote(name),
+                    quote(separator)
+                )
+            }
+            Expression::Concat(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " & ")?;
+                    }
+                    write!(f, "{term}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Declaration inside a project, package or case alternative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Declaration {
+    Attribute {
+        name: String,
+        index: Option<String>,
+        value: Expression,
+    },
+    Type {
+        name: String,
+        values: Vec<String>,
+    },
+    Variable {
+        name: String,
+        type_name: Option<String>,
+        value: Expression,
+    },
+    Package(Package),
+    Case(Case),
+}
+
+impl Declaration {
+    pub fn attribute(name: &str, value: impl Into<Expression>) -> Declaration {
+        Declaration::Attribute {
+            name: String::from(name),
+            index: None,
+            value: value.into(),
+        }
+    }
+
+    pub fn indexed_attribute(name: &str, index: &str, value: impl Into<Expression>) -> Declaration {
+        Declaration::Attribute {
+            name: String::from(name),
+            index: Some(String::from(index)),
+            value: value.into(),
+        }
+    }
+
+    pub fn type_declaration(name: &str, values: &[&str]) -> Declaration {
+        Declaration::Type {
+            name: String::from(name),
+            values: values.iter().map(|v| String::from(*v)).collect(),
+        }
+    }
+
+    pub fn variable(name: &str, value: impl Into<Expression>) -> Declaration {
+        Declaration::Variable {
+            name: String::from(name),
+            type_name: None,
+            value: value.into(),
+        }
+    }
+
+    pub fn typed_variable(
+        name: &str,
+        type_name: &str,
+        value: impl Into<Expression>,
+    ) -> Declaration {
+        Declaration::Variable {
+            name: String::from(name),
+            type_name: Some(String::from(type_name)),
+            value: value.into(),
+        }
+    }
+
+    /// Packages and case statements are separated from the surrounding
+    /// declarations by blank lines.
+    fn is_block(&self) -> bool {
+        matches!(self, Declaration::Package(_) | Declaration::Case(_))
+    }
+
+    fn render(&self, code: &mut String, indent: usize) {
+        let pad = " ".repeat(indent);
+        match self {
+            Declaration::Attribute { name, index, value } => match index {
+                Some(index) => {
+                    let _ = writeln!(code, "{pad}for {name} ({}) use {value};", quote(index));
+                }
+                None => {
+                    let _ = writeln!(code, "{pad}for {name} use {value};");
+                }
+            },
+            Declaration::Type { name, values } => {
+                let values: Vec<String> = values.iter().map(|v| quote(v)).collect();
+                let _ = writeln!(code, "{pad}type {name} is ({});", values.join(", "));
+            }
+            Declaration::Variable {
+                name,
+                type_name,
+                value,
+            } => match type_name {
+                Some(type_name) => {
+                    let _ = writeln!(code, "{pad}{name} : {type_name} := {value};");
+                }
+                None => {
+                    let _ = writeln!(code, "{pad}{name} := {value};");
+                }
+            },
+            Declaration::Package(package) => {
+                let _ = writeln!(code, "{pad}package {} is", package.name);
+                render_block(&package.declarations, code, indent + 3);
+                let _ = writeln!(code, "{pad}end {};", package.name);
+            }
+            Declaration::Case(case) => {
+                let _ = writeln!(code, "{pad}case {} is", case.variable);
+                for (values, declarations) in &case.alternatives {
+                    let choice = if values.is_empty() {
+                        String::from("others")
+                    } else {
+                        values
+                            .iter()
+                            .map(|v| quote(v))
+                            .collect::<Vec<_>>()
+                            .join(" | ")
+                    };
+                    let _ = writeln!(code, "{pad}   when {choice} =>");
+                    if declarations.is_empty() {
+                        let _ = writeln!(code, "{pad}      null;");
+                    } else {
+                        render_block(declarations, code, indent + 6);
+                    }
+                }
+                let _ = writeln!(code, "{pad}end case;");
+            }
+        }
+    }
+}
+
+fn render_block(declarations: &[Declaration], code: &mut String, indent: usize) {
+    for (i, declaration) in declarations.iter().enumerate() {
+        if i > 0 && (declaration.is_block() || declarations[i - 1].is_block()) {
+            code.push('\n');
+        }
+        declaration.render(code, indent);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    name: String,
+    declarations: Vec<Declaration>,
+}
+
+impl Package {
+    pub fn new(name: &str) -> Package {
+        Package {
+            name: String::from(name),
+            declarations: Vec::new(),
+        }
+    }
+
+    pub fn declare(mut self, declaration: Declaration) -> Package {
+        self.declarations.push(declaration);
+        self
+    }
+
+    pub fn attribute(self, name: &str, value: impl Into<Expression>) -> Package {
+        self.declare(Declaration::attribute(name, value))
+    }
+
+    pub fn indexed_attribute(
+        self,
+        name: &str,
+        index: &str,
+        value: impl Into<Expression>,
+    ) -> Package {
+        self.declare(Declaration::indexed_attribute(name, index, value))
+    }
+}
+
+/// Case statement over a typed variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    variable: String,
+    alternatives: Vec<(Vec<String>, Vec<Declaration>)>,
+}
+
+impl Case {
+    pub fn new(variable: &str) -> Case {
+        Case {
+            variable: String::from(variable),
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Adds an alternative for `values`, an empty slice adds `when others`.
+    pub fn when(mut self, values: &[&str], declarations: Vec<Declaration>) -> Case {
+        self.alternatives.push((
+            values.iter().map(|v| String::from(*v)).collect(),
+            declarations,
+        ));
+        self
+    }
+}
+
+/// Project file that can be rendered as GPR source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectFile {
+    name: String,
+    kind: ProjectKind,
+    comments: Vec<String>,
+    withs: Vec<String>,
+    extends: Option<(String, bool)>,
+    declarations: Vec<Declaration>,
+}
+
+impl ProjectFile {
+    pub fn new(name: &str, kind: ProjectKind) -> ProjectFile {
+        ProjectFile {
+            name: String::from(name),
+            kind,
+            comments: Vec::new(),
+            withs: Vec::new(),
+            extends: None,
+            declarations: Vec::new(),
+        }
+    }
+
+    /// Adds a comment line to the header of the file.
+    pub fn comment(mut self, comment: &str) -> ProjectFile {
+        self.comments.push(String::from(comment));
+        self
+    }
+
+    pub fn with(mut self, project: &str) -> ProjectFile {
+        self.withs.push(String::from(project));
+        self
+    }
+
+    pub fn extends(mut self, project: &str) -> ProjectFile {
+        self.extends = Some((String::from(project), false));
+        self
+    }
+
+    pub fn extends_all(mut self, project: &str) -> ProjectFile {
+        self.extends = Some((String::from(project), true));
+        self
+    }
+
+    pub fn declare(mut self, declaration: Declaration) -> ProjectFile {
+        self.declarations.push(declaration);
+        self
+    }
+
+    pub fn attribute(self, name: &str, value: impl Into<Expression>) -> ProjectFile {
+        self.declare(Declaration::attribute(name, value))
+    }
+
+    pub fn indexed_attribute(
+        self,
+        name: &str,
+        index: &str,
+        value: impl Into<Expression>,
+    ) -> ProjectFile {
+        self.declare(Declaration::indexed_attribute(name, index, value))
+    }
+
+    pub fn package(self, package: Package) -> ProjectFile {
+        self.declare(Declaration::Package(package))
+    }
+
+    pub fn case(self, case: Case) -> ProjectFile {
+        self.declare(Declaration::Case(case))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes the project to `<dir>/<name>.gpr` with the name in lower case.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, error::Error> {
+        let file = dir.join(format!("{}.gpr", self.name.to_lowercase()));
+        fs::write(&file, self.to_string())?;
+        Ok(file)
+    }
+}
+
+impl fmt::Display for ProjectFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for comment in &self.comments {
+            writeln!(f, "--  {comment}")?;
+        }
+        if !self.comments.is_empty() {
+            writeln!(f)?;
+        }
+        for with in &self.withs {
+            writeln!(f, "with {};", quote(with))?;
+        }
+        if !self.withs.is_empty() {
+            writeln!(f)?;
+        }
+        write!(f, "{} {}", self.kind, self.name)?;
+        match &self.extends {
+            Some((project, true)) => write!(f, " extends all {}", quote(project))?,
+            Some((project, false)) => write!(f, " extends {}", quote(project))?,
+            None => {}
+        }
+        writeln!(f, " is\n")?;
+        let mut code = String::new();
+        render_block(&self.declarations, &mut code, 3);
+        if !code.is_empty() {
+            writeln!(f, "{code}")?;
+        }
+        writeln!(f, "end {};", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let project = ProjectFile::new("Demo", ProjectKind::Library)
+            .with("shared.gpr")
+            .extends("base \"1\".gpr")
+            .declare(Declaration::type_declaration(
+                "Mode_Type",
+                &["debug", "release"],
+            ))
+            .declare(Declaration::typed_variable(
+                "Mode",
+                "Mode_Type",
+                Expression::External {
+                    name: String::from("MODE"),
+                    default: Some(String::from("debug")),
+                },
+            ))
+            .attribute("Source_Dirs", ["src", "gen"])
+            .attribute(
+                "Object_Dir",
+                Expression::Concat(vec!["obj/".into(), Expression::Reference("Mode".into())]),
+            )
+            .case(
+                Case::new("Mode")
+                    .when(
+                        &["debug"],
+                        vec![Declaration::attribute("Library_Kind", "static")],
+                    )
+                    .when(&[], vec![]),
+            )
+            .package(Package::new("Compiler").indexed_attribute(
+                "Default_Switches",
+                "Ada",
+                Expression::ExternalAsList {
+                    name: String::from("CFLAGS"),
+                    separator: String::from(" "),
+                },
+            ));
+        assert_eq!(
+            project.to_string(),
+            "with \"shared.gpr\";
+
+library project Demo extends \"base \"\"1\"\".gpr\" is
+
+   type Mode_Type is (\"debug\", \"release\");
+   Mode : Mode_Type := external (\"MODE\", \"debug\");
+   for Source_Dirs use (\"src\", \"gen\");
+   for Object_Dir use \"obj/\" & Mode;
+
+   case Mode is
+      when \"debug\" =>
+         for Library_Kind use \"static\";
+      when others =>
+         null;
+   end case;
+
+   package Compiler is
+      for Default_Switches (\"Ada\") use external_as_list (\"CFLAGS\", \" \");
+   end Compiler;
+
+end Demo;
+"
+        );
+    }
+
+    #[test]
+    fn test_ada_name() {
+        assert_eq!(ada_name("point_type"), "Point_Type");
+        assert_eq!(ada_name("PointType"), "Point_Type");
+        assert_eq!(ada_name("r#type"), "Type");
+        assert_eq!(ada_name("_private__value_"), "Private_Value");
+        assert_eq!(ada_name("u8Value"), "U8_Value");
+    }
+
+    #[test]
+    fn test_kind_from_view() {
+        let file = Path::new("test.gpr");
+        assert_eq!(
+            ProjectKind::from_view(file, "K_Aggregate_Library").unwrap(),
+            ProjectKind::AggregateLibrary
+        );
+        assert!(ProjectKind::from_view(file, "k_configuration").is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(
+            ProjectFile::new("Empty", ProjectKind::Abstract)
+                .comment("generated")
+                .to_string(),
+            "--  generated\n\nabstract project Empty is\n\nend Empty;\n"
+        );
+    }
+}
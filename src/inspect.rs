@@ -0,0 +1,278 @@
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{
+    backend::{self, attribute_key, Attributes, Snapshot},
+    binding::{AttributeValue, Dirs, View},
+    error,
+};
+
+/// Output of `gprinspect --display=json`. Fields that are not needed are
+/// ignored.
+#[derive(Debug, Deserialize)]
+struct Output {
+    tree: TreeInfo,
+    projects: Vec<ProjectEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TreeInfo {
+    root_project: String,
+    target: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectEntry {
+    project: ProjectInfo,
+    #[serde(default)]
+    attributes: Vec<AttributeEntry>,
+    #[serde(default)]
+    packages: Vec<PackageEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectInfo {
+    name: String,
+    path: String,
+    /// Kind in words, e.g. `aggregate library`.
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageEntry {
+    package: String,
+    attributes: Vec<AttributeEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AttributeEntry {
+    name: String,
+    index: Option<String>,
+    value: AttributeValue,
+}
+
+fn read_attributes(package: Option<&str>, list: &[AttributeEntry], attributes: &mut Attributes) {
+    for attribute in list {
+        attributes.insert(
+            attribute_key(package, &attribute.name, attribute.index.as_deref()),
+            attribute.value.clone(),
+        );
+    }
+}
+
+/// Runs `gprinspect` from `PATH` on `file` with the externals in `context`.
+pub fn load(
+    file: &Path,
+    context: &BTreeMap<String, String>,
+    config: Option<&Path>,
+    src_subdirs: Option<&str>,
+) -> Result<Snapshot, error::Error> {
+    let mut cmd = Command::new("gprinspect");
+    cmd.arg("-P")
+        .arg(file)
+        .args(["--display=json", "--all", "-r"]);
+    if let Some(config) = config {
+        let mut arg = OsString::from("--config=");
+        arg.push(config);
+        cmd.arg(arg);
+    }
+    if let Some(src_subdirs) = src_subdirs {
+        cmd.arg(format!("--src-subdirs={src_subdirs}"));
+    }
+    cmd.args(context.iter().map(|(k, v)| format!("-X{k}={v}")));
+    let result = cmd.output()?;
+    if !result.status.success() {
+        return Err(error::Error::command(
+            "gprinspect",
+            &String::from_utf8_lossy(&result.stderr),
+        ));
+    }
+    let mut tree = parse(&String::from_utf8_lossy(&result.stdout))?;
+    read_extensions(&mut tree);
+    tree.src_subdirs = src_subdirs.map(String::from);
+    tree.context = context.clone().into_iter().collect();
+    Ok(tree)
+}
+
+/// Fills in the projects extended by the views of `tree`, which gprinspect
+/// does not report. Extended projects are looked up next to the extending
+/// project, in the directories of the other views and along
+/// `GPR_PROJECT_PATH`. Views whose `extends` clause cannot be read are left
+/// as they are, gprinspect already accepted the tree.
+fn read_extensions(tree: &mut Snapshot) {
+    let dirs: Vec<PathBuf> = tree
+        .view_data
+        .values()
+        .map(|(view, _)| view.dir.clone())
+        .collect();
+    for (view, _) in tree.view_data.values_mut() {
+        if let Ok(Some((extended, all))) = backend::extension(&view.path, &dirs) {
+            view.extended = Some(extended);
+            view.extends_all = all;
+        }
+    }
+}
+
+/// Parses the output of `gprinspect --display=json`.
+pub fn parse(output: &str) -> Result<Snapshot, error::Error> {
+    let output: Output = serde_json::from_str(output)
+        .map_err(|e| error::Error::invalid_response("gprinspect", &e.to_string()))?;
+    let mut tree = Snapshot {
+        root_view: output.tree.root_project,
+        target: output.tree.target,
+        ..Snapshot::default()
+    };
+    for entry in output.projects {
+        let mut attributes = Attributes::new();
+        read_attributes(None, &entry.attributes, &mut attributes);
+        for package in &entry.packages {
+            read_attributes(Some(&package.package), &package.attributes, &mut attributes);
+        }
+        let info = entry.project;
+        let path = PathBuf::from(&info.path);
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        tree.add_view(
+            View {
+                id: info.path,
+                name: info.name,
+                path,
+                dir,
+                kind: format!("k_{}", info.kind.to_lowercase().replace(' ', "_")),
+                extended: None,
+                extends_all: false,
+                dirs: Dirs::default(),
+            },
+            attributes,
+        );
+    }
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let tree =
+            parse(&std::fs::read_to_string("testdata/gprinspect/testlib.json").unwrap()).unwrap();
+        assert_eq!(tree.root_view, "/prj/testlib.gpr");
+        assert_eq!(tree.views.len(), 2);
+        assert_eq!(tree.config_view.as_deref(), Some("/prj/obj/auto.cgpr"));
+        assert_eq!(tree.target, "x86_64-linux");
+        let view = tree.view("/prj/testlib.gpr").unwrap();
+        assert_eq!(view.name, "Testlib");
+        assert_eq!(view.dir, Path::new("/prj"));
+        assert!(view.is_library());
+        let attr = |view, pkg, name, index| match tree
+            .get_view_attribute(view, pkg, name, index)
+            .unwrap()
+            .value
+        {
+            AttributeValue::Single(value) => vec![value],
+            AttributeValue::List(values) => values,
+        };
+        assert_eq!(
+            attr("/prj/testlib.gpr", None, "library_name", None),
+            vec!["testlib"]
+        );
+        assert_eq!(
+            attr("/prj/testlib.gpr", None, "Source_Dirs", None),
+            vec!["src"]
+        );
+        assert_eq!(attr("/prj/common.gpr", None, "object_dir", None), vec!["."]);
+        assert_eq!(
+            attr(
+                "/prj/testlib.gpr",
+                Some("Compiler"),
+                "default_switches",
+                Some("ada")
+            ),
+            vec!["-O2", "-gnatwa"]
+        );
+        assert_eq!(
+            attr("/prj/obj/auto.cgpr", None, "runtime_dir", Some("Ada")),
+            vec!["/opt/gnat/lib/gcc/x86_64-linux/rts-native"]
+        );
+        assert!(matches!(
+            tree.get_view_attribute("/prj/testlib.gpr", None, "main", None),
+            Err(error::Error::AttributeNotFound(_))
+        ));
+        assert!(matches!(
+            parse("{}"),
+            Err(error::Error::InvalidResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_read_extensions() {
+        let dir = std::env::temp_dir()
+            .canonicalize()
+            .unwrap()
+            .join(format!("gpr_rust_inspect_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("base")).unwrap();
+        std::fs::write(dir.join("base/base.gpr"), "project Base is\nend Base;\n").unwrap();
+        std::fs::write(
+            dir.join("ext.gpr"),
+            "project Ext extends all \"base\" is\nend Ext;\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("bad.gpr"), "project Bad is ???\n").unwrap();
+        let project = |name: &str| {
+            format!(
+                r#"{{ "project": {{ "name": "{name}", "path": "{}", "kind": "standard" }} }}"#,
+                dir.join(format!("{name}.gpr")).display()
+            )
+        };
+        let mut tree = parse(&format!(
+            r#"{{ "tree": {{ "root-project": "{}", "target": "x86_64-linux" }},
+                  "projects": [{}, {}, {}] }}"#,
+            dir.join("ext.gpr").display(),
+            project("ext"),
+            project("base/base"),
+            project("bad"),
+        ))
+        .unwrap();
+        read_extensions(&mut tree);
+        let view = |name: &str| tree.view(&dir.join(name).display().to_string()).unwrap();
+        assert_eq!(view("ext.gpr").extended, Some(dir.join("base/base.gpr")));
+        assert!(view("ext.gpr").extends_all);
+        assert_eq!(view("base/base.gpr").extended, None);
+        assert_eq!(view("bad.gpr").extended, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_gprinspect() {
+        let found = std::env::var_os("PATH").is_some_and(|path| {
+            std::env::split_paths(&path)
+                .any(|dir| dir.join("gprinspect").is_file() || dir.join("gprinspect.exe").is_file())
+        });
+        if !found {
+            return;
+        }
+        let file = Path::new("testdata/testlib.gpr").canonicalize().unwrap();
+        let tree = load(&file, &BTreeMap::new(), None, None).unwrap();
+        assert_eq!(Path::new(&tree.root_view), file);
+        let view = tree.view(&tree.root_view).unwrap();
+        assert!(view.is_library());
+        assert!(matches!(
+            tree.get_view_attribute(&tree.root_view, None, "Library_Name", None)
+                .unwrap()
+                .value,
+            AttributeValue::Single(name) if name == "test"
+        ));
+        assert!(matches!(
+            tree.get_view_attribute(&tree.root_view, None, "source_dirs", None)
+                .unwrap()
+                .value,
+            AttributeValue::List(_)
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Whitespace,
+    Comment,
+    Identifier,
+    String,
+    /// Ada character literal, e.g. `'"'`.
+    Character,
+    Number,
+    LeftParen,
+    RightParen,
+    Comma,
+    Semicolon,
+    Colon,
+    Assign,
+    Arrow,
+    Ampersand,
+    Dot,
+    Pipe,
+    Apostrophe,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token<'_> {
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::Comment)
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Identifier && self.text.eq_ignore_ascii_case(keyword)
+    }
+
+    /// Value of a string literal with the surrounding quotes removed and
+    /// doubled quotes collapsed.
+    pub fn string_value(&self) -> Option<String> {
+        if self.kind != TokenKind::String {
+            return None;
+        }
+        let inner = self.text.strip_prefix('"')?;
+        let inner = inner.strip_suffix('"').unwrap_or(inner);
+        Some(inner.replace("\"\"", "\""))
+    }
+}
+
+/// Ada reserved words after which an apostrophe starts a character literal
+/// rather than an attribute. `all` is missing as in `X.all'Access`.
+const RESERVED: &[&str] = &[
+    "abort",
+    "abs",
+    "abstract",
+    "accept",
+    "access",
+    "aliased",
+    "and",
+    "array",
+    "at",
+    "begin",
+    "body",
+    "case",
+    "constant",
+    "declare",
+    "delay",
+    "delta",
+    "digits",
+    "do",
+    "else",
+    "elsif",
+    "end",
+    "entry",
+    "exception",
+    "exit",
+    "for",
+    "function",
+    "generic",
+    "goto",
+    "if",
+    "in",
+    "interface",
+    "is",
+    "limited",
+    "loop",
+    "mod",
+    "new",
+    "not",
+    "null",
+    "of",
+    "or",
+    "others",
+    "out",
+    "overriding",
+    "package",
+    "pragma",
+    "private",
+    "procedure",
+    "protected",
+    "raise",
+    "range",
+    "record",
+    "rem",
+    "renames",
+    "requeue",
+    "return",
+    "reverse",
+    "select",
+    "separate",
+    "some",
+    "subtype",
+    "synchronized",
+    "tagged",
+    "task",
+    "terminate",
+    "then",
+    "type",
+    "until",
+    "use",
+    "when",
+    "while",
+    "with",
+    "xor",
+];
+
+/// Length of the Ada character literal at `offset` of `source`, if there is
+/// one. An apostrophe after a name or a closing parenthesis is an attribute.
+fn character_literal(source: &str, offset: usize, previous: Option<&Token>) -> Option<usize> {
+    let attribute = previous.is_some_and(|t| {
+        t.kind == TokenKind::RightParen
+            || (t.kind == TokenKind::Identifier
+                && !RESERVED.iter().any(|r| t.text.eq_ignore_ascii_case(r)))
+    });
+    let c = source[offset + 1..].chars().next()?;
+    let end = offset + 1 + c.len_utf8();
+    (!attribute && source[end..].starts_with('\'')).then_some(end + 1 - offset)
+}
+
+/// Splits GPR source text into tokens. The lexer never fails, characters
+/// that are not part of the language end up in `Unknown` tokens so that
+/// concatenating all token texts always yields the original source.
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    lex(source, false)
+}
+
+/// Splits Ada source text into tokens. Ada shares its lexical structure
+/// with GPR, but also has character literals.
+pub(crate) fn tokenize_ada(source: &str) -> Vec<Token<'_>> {
+    lex(source, true)
+}
+
+fn lex(source: &str, ada: bool) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let bytes = source.as_bytes();
+    let mut offset = 0;
+    let mut line = 1;
+    let mut column = 1;
+    while offset < bytes.len() {
+        let start = offset;
+        let c = bytes[offset];
+        let literal = (ada && c == b'\'')
+            .then(|| character_literal(source, offset, tokens.iter().rfind(|t| !t.is_trivia())))
+            .flatten();
+        let kind = match c {
+            b'\'' if literal.is_some() => {
+                offset += literal.unwrap_or(1);
+                TokenKind::Character
+            }
+            b' ' | b'\t' | b'\r' | b'\n' | 0x0b | 0x0c => {
+                while offset < bytes.len() && bytes[offset].is_ascii_whitespace() {
+                    offset += 1;
+                }
+                TokenKind::Whitespace
+            }
+            b'-' if bytes.get(offset + 1) == Some(&b'-') => {
+                while offset < bytes.len() && bytes[offset] != b'\n' {
+                    offset += 1;
+                }
+                TokenKind::Comment
+            }
+            b'"' => {
+                offset += 1;
+                loop {
+                    match bytes.get(offset) {
+                        Some(b'"') if bytes.get(offset + 1) == Some(&b'"') => offset += 2,
+                        Some(b'"') => {
+                            offset += 1;
+                            break TokenKind::String;
+                        }
+                        Some(b'\n') | None => break TokenKind::Unknown,
+                        Some(_) => offset += 1,
+                    }
+                }
+            }
+            c if c.is_ascii_alphabetic() => {
+                while offset < bytes.len()
+                    && (bytes[offset].is_ascii_alphanumeric() || bytes[offset] == b'_')
+                {
+                    offset += 1;
+                }
+                TokenKind::Identifier
+            }
+            c if c.is_ascii_digit() => {
+                while offset < bytes.len()
+                    && (bytes[offset].is_ascii_alphanumeric() || bytes[offset] == b'_')
+                {
+                    offset += 1;
+                }
+                TokenKind::Number
+            }
+            b':' if bytes.get(offset + 1) == Some(&b'=') => {
+                offset += 2;
+                TokenKind::Assign
+            }
+            b'=' if bytes.get(offset + 1) == Some(&b'>') => {
+                offset += 2;
+                TokenKind::Arrow
+            }
+            _ => {
+                offset += 1;
+                match c {
+                    b'(' => TokenKind::LeftParen,
+                    b')' => TokenKind::RightParen,
+                    b',' => TokenKind::Comma,
+                    b';' => TokenKind::Semicolon,
+                    b':' => TokenKind::Colon,
+                    b'&' => TokenKind::Ampersand,
+                    b'.' => TokenKind::Dot,
+                    b'|' => TokenKind::Pipe,
+                    b'\'' => TokenKind::Apostrophe,
+                    _ => {
+                        while !source.is_char_boundary(offset) {
+                            offset += 1;
+                        }
+                        TokenKind::Unknown
+                    }
+                }
+            }
+        };
+        let text = &source[start..offset];
+        tokens.push(Token {
+            kind,
+            text,
+            offset: start,
+            line,
+            column,
+        });
+        for ch in text.chars() {
+            if ch == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        tokenize(source)
+            .iter()
+            .filter(|t| !t.is_trivia())
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            kinds("V : T := external (\"X\", \"y\"); -- comment"),
+            vec![
+                TokenKind::Identifier,
+                TokenKind::Colon,
+                TokenKind::Identifier,
+                TokenKind::Assign,
+                TokenKind::Identifier,
+                TokenKind::LeftParen,
+                TokenKind::String,
+                TokenKind::Comma,
+                TokenKind::String,
+                TokenKind::RightParen,
+                TokenKind::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_character_literal() {
+        let source = "Quote : constant Character := '\"'; X : T := T'First; \
+                      C : Character := Character'('é'); P := X.all'Access; when 'a' =>";
+        let tokens: Vec<_> = tokenize_ada(source)
+            .into_iter()
+            .filter(|t| matches!(t.kind, TokenKind::Character | TokenKind::Apostrophe))
+            .map(|t| (t.kind, t.text))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenKind::Character, "'\"'"),
+                (TokenKind::Apostrophe, "'"),
+                (TokenKind::Apostrophe, "'"),
+                (TokenKind::Character, "'é'"),
+                (TokenKind::Apostrophe, "'"),
+                (TokenKind::Character, "'a'"),
+            ]
+        );
+        assert_eq!(
+            kinds("for X use Project'Name;")
+                .iter()
+                .filter(|k| **k == TokenKind::Character)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_lossless() {
+        let source = "project P is\n   for X use \"a\"\"b\"; -- c\nend P;\n\"open";
+        let tokens = tokenize(source);
+        assert_eq!(tokens.iter().map(|t| t.text).collect::<String>(), source);
+        assert_eq!(tokens.last().unwrap().kind, TokenKind::Unknown);
+        assert_eq!(tokens.last().unwrap().line, 4);
+    }
+
+    #[test]
+    fn test_string_value() {
+        let tokens = tokenize("\"a\"\"b\"");
+        assert_eq!(tokens[0].string_value().unwrap(), "a\"b");
+    }
+}
@@ -1,31 +1,127 @@
 use std::{
+    collections::BTreeMap,
+    ffi::OsString,
     fmt,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
-#[macro_use]
-extern crate enum_display_derive;
-
 #[macro_use]
 extern crate lazy_static;
 
+#[cfg(feature = "ada-bindings")]
+mod adagen;
+mod ast;
+mod backend;
+mod bindgen;
 mod binding;
+mod config;
+mod editor;
+mod elaboration;
 mod error;
+mod evaluator;
+mod formatter;
+mod gprgen;
+mod inspect;
+mod lexer;
+mod link;
+mod parser;
+mod paths;
+mod projectset;
+mod rustlib;
+mod scenario;
+mod sources;
+mod toolchain;
 
+pub use self::backend::Backend;
 pub use self::binding::{finalize, initialize};
+pub use self::config::Configuration;
+pub use self::editor::ProjectEditor;
+pub use self::error::{Error, Exception, Location};
+pub use self::formatter::{check_formatting, format_file, format_source, Difference};
+pub use self::gprgen::{Case, Declaration, Expression, Package, ProjectFile, ProjectKind};
+pub use self::link::{Directive, LinkOptions, Rpath};
+pub use self::projectset::ProjectSet;
+pub use self::rustlib::{native_static_libs, RustLibrary};
+pub use self::scenario::{
+    scenario_variables, Combination, Combinations, Failure, Scenario, ScenarioVariable,
+};
+pub use self::toolchain::{compilers, find_ada_compiler, Compiler};
 
 #[derive(Debug)]
 pub struct Project {
     file: PathBuf,
-    tree: binding::Tree,
+    tree: backend::Tree,
+    context: BTreeMap<String, String>,
+    config: Configuration,
+    views: OnceLock<Vec<ProjectView>>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Subdirectory of the object directories that contains the generated Ada
+/// sources, passed to libgpr2 and gprbuild as `--src-subdirs`.
+#[cfg(feature = "ada-bindings")]
+const SRC_SUBDIRS: &str = "gpr-rust";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum LibraryKind {
     Static,
     Dynamic,
 }
 
+/// Library built by one of the projects of a project tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    pub name: String,
+    pub dir: PathBuf,
+    pub kind: LibraryKind,
+    /// Project file of the library project.
+    pub project: PathBuf,
+}
+
+/// Project aggregated by an aggregate or aggregate library project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aggregated {
+    pub name: String,
+    pub project: PathBuf,
+    pub kind: ProjectKind,
+    /// Externals the project is loaded with, i.e. the externals of the
+    /// aggregate project overridden by its `External` attributes.
+    pub externals: BTreeMap<String, String>,
+}
+
+/// Project of the loaded project tree together with its extension
+/// relationships.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectView {
+    pub name: String,
+    pub project: PathBuf,
+    pub kind: ProjectKind,
+    /// Project extended by this project.
+    pub extended: Option<PathBuf>,
+    /// Whether the project is declared with `extends all`.
+    pub extends_all: bool,
+    /// Project extending this project.
+    pub extending: Option<PathBuf>,
+}
+
+/// Source of an extending project that replaces a source of the same name
+/// in an extended project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverriddenSource {
+    pub source: PathBuf,
+    pub overridden: PathBuf,
+}
+
+impl LibraryKind {
+    fn from_attribute(file: &Path, value: &str) -> Result<LibraryKind, error::Error> {
+        match value {
+            "static" | "static-pic" => Ok(LibraryKind::Static),
+            "dynamic" | "relocatable" => Ok(LibraryKind::Dynamic),
+            value => Err(error::Error::invalid_attribute(file, "library_kind", value)),
+        }
+    }
+}
+
 impl fmt::Display for LibraryKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -49,15 +145,61 @@ macro_rules! list {
 
 impl Project {
     pub fn load(file: &Path) -> Result<Project, error::Error> {
-        let tree = binding::Tree::load(file)?;
+        Project::load_with_scenario(file, &Scenario::new())
+    }
+
+    /// Loads the project with the externals defined by `scenario`. Each value
+    /// is checked against the typed scenario variables declared in the project
+    /// tree before the project is loaded.
+    pub fn load_with_scenario(file: &Path, scenario: &Scenario) -> Result<Project, error::Error> {
+        Project::load_with_config(file, scenario, &Configuration::Auto)
+    }
+
+    /// Loads the project with the toolchain configuration `config`. The same
+    /// configuration is passed on to gprbuild. The backend is selected by
+    /// [`Backend::from_env`].
+    pub fn load_with_config(
+        file: &Path,
+        scenario: &Scenario,
+        config: &Configuration,
+    ) -> Result<Project, error::Error> {
+        Project::load_with_backend(file, scenario, config, Backend::from_env()?)
+    }
+
+    /// Like [`Project::load_with_config`] but loads the project tree with
+    /// `backend`.
+    pub fn load_with_backend(
+        file: &Path,
+        scenario: &Scenario,
+        config: &Configuration,
+        backend: Backend,
+    ) -> Result<Project, error::Error> {
+        let context = scenario.context();
+        if !context.is_empty() {
+            scenario::Declarations::scan(file)?.validate(&context)?;
+        }
+        let tree = backend::Tree::load(backend, file, &context, config.file(), None)?;
         Ok(Project {
             file: file.canonicalize()?,
             tree,
+            context,
+            config: config.clone(),
+            views: OnceLock::new(),
         })
     }
 
-    fn basepath(&self) -> PathBuf {
-        self.file.as_path().parent().unwrap().to_path_buf()
+    /// Identifiers of all views of the tree, starting with the root view.
+    fn view_ids(&self) -> Vec<&str> {
+        let root = self.tree.root_view();
+        std::iter::once(root)
+            .chain(
+                self.tree
+                    .views()
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|v| *v != root),
+            )
+            .collect()
     }
 
     fn get_single_attribute_value(
@@ -92,44 +234,508 @@ impl Project {
         single!(self, "name")
     }
 
+    pub fn kind(&self) -> Result<ProjectKind, error::Error> {
+        let view = self.tree.view(self.tree.root_view())?;
+        ProjectKind::from_view(&self.file, &view.kind)
+    }
+
+    /// Fails for aggregate projects, which do not build a library
+    /// themselves.
+    fn check_library(&self) -> Result<(), error::Error> {
+        if self.kind()? == ProjectKind::Aggregate {
+            return Err(error::Error::no_library(&self.file));
+        }
+        Ok(())
+    }
+
     pub fn library_name(&self) -> Result<String, error::Error> {
+        self.check_library()?;
         single!(self, "library_name")
     }
 
+    /// Returns the absolute paths of a path attribute of the root project
+    /// such as `source_dirs`, `object_dir` or `exec_dir`, as resolved by the
+    /// backend. Relative paths are resolved against the directory of the
+    /// project each value comes from and recursive directories (`dir/**`)
+    /// are expanded.
+    pub fn path_attribute(&self, name: &str) -> Result<Vec<PathBuf>, error::Error> {
+        self.tree.path_values(self.tree.root_view(), name)
+    }
+
+    /// Resolved value of the single path attribute `name` of the view `id`.
+    fn single_path_value(&self, id: &str, name: &str) -> Result<PathBuf, error::Error> {
+        let value = self.get_single_attribute_value(
+            name,
+            self.tree.get_view_attribute(id, None, name, None)?.value,
+        )?;
+        self.tree
+            .path_values(id, name)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| error::Error::invalid_attribute(&self.file, name, &value))
+    }
+
+    fn single_path_attribute(&self, name: &str) -> Result<PathBuf, error::Error> {
+        self.single_path_value(self.tree.root_view(), name)
+    }
+
     pub fn library_dir(&self) -> Result<PathBuf, error::Error> {
-        Ok(self
-            .basepath()
-            .as_path()
-            .join(single!(self, "library_dir")?))
+        self.check_library()?;
+        self.single_path_attribute("library_dir")
+    }
+
+    pub fn object_dir(&self) -> Result<PathBuf, error::Error> {
+        self.single_path_attribute("object_dir")
     }
 
     pub fn library_kind(&self) -> Result<LibraryKind, error::Error> {
-        match single!(self, "library_kind")?.as_str() {
-            "static" | "static-pic" => Ok(LibraryKind::Static),
-            "dynamic" | "relocatable" => Ok(LibraryKind::Dynamic),
-            value => Err(error::Error::invalid_attribute(
+        self.check_library()?;
+        LibraryKind::from_attribute(&self.file, single!(self, "library_kind")?.as_str())
+    }
+
+    /// Returns the libraries of all library projects in the project tree,
+    /// starting with the root project.
+    pub fn libraries(&self) -> Result<Vec<Library>, error::Error> {
+        let mut libraries = Vec::new();
+        for id in self.view_ids() {
+            let view = self.tree.view(id)?;
+            if !view.is_library() {
+                continue;
+            }
+            let path = view.path;
+            let attr = |name| -> Result<String, error::Error> {
+                self.get_single_attribute_value(
+                    name,
+                    self.tree.get_view_attribute(id, None, name, None)?.value,
+                )
+            };
+            libraries.push(Library {
+                name: attr("library_name")?,
+                dir: self.single_path_value(id, "library_dir")?,
+                kind: LibraryKind::from_attribute(&path, &attr("library_kind")?)?,
+                project: path,
+            });
+        }
+        Ok(libraries)
+    }
+
+    /// Returns all projects of the tree, starting with the root project.
+    pub fn views(&self) -> Result<Vec<ProjectView>, error::Error> {
+        if let Some(views) = self.views.get() {
+            return Ok(views.clone());
+        }
+        let mut views = Vec::new();
+        for id in self.view_ids() {
+            let view = self.tree.view(id)?;
+            let path = view.path.canonicalize().unwrap_or(view.path.clone());
+            let extension = self.tree.extension(&view)?;
+            views.push(ProjectView {
+                name: view.name,
+                kind: ProjectKind::from_view(&path, &view.kind)?,
+                extended: extension.as_ref().map(|(extended, _)| extended.clone()),
+                extends_all: extension.is_some_and(|(_, all)| all),
+                extending: None,
+                project: path,
+            });
+        }
+        for i in 0..views.len() {
+            views[i].extending = views
+                .iter()
+                .find(|v| v.extended.as_ref() == Some(&views[i].project))
+                .map(|v| v.project.clone());
+        }
+        Ok(self.views.get_or_init(|| views).clone())
+    }
+
+    /// Returns the project file that declares the attribute `name` of the
+    /// root project, which is an extended project if the attribute is
+    /// inherited. `None` is returned if the attribute has its default value.
+    pub fn attribute_project(
+        &self,
+        package: Option<&str>,
+        name: &str,
+    ) -> Result<Option<PathBuf>, error::Error> {
+        Ok(self
+            .tree
+            .origin(self.tree.root_view(), package, name, None)?
+            .map(|origin| origin.project))
+    }
+
+    /// Returns the sources of extending projects that replace sources with
+    /// the same file name in the projects they extend.
+    pub fn overridden_sources(&self) -> Result<Vec<OverriddenSource>, error::Error> {
+        let views = self.views()?;
+        let ids = self.view_ids();
+        let sources = |project: &Path| -> Result<Vec<PathBuf>, error::Error> {
+            match views.iter().position(|v| v.project == project) {
+                Some(i) => self.tree.sources(ids[i]),
+                None => Ok(Vec::new()),
+            }
+        };
+        let mut overridden = Vec::new();
+        for view in &views {
+            let Some(extended) = &view.extended else {
+                continue;
+            };
+            let own = sources(&view.project)?;
+            let mut current = Some(extended.clone());
+            while let Some(project) = current {
+                for source in sources(&project)? {
+                    if let Some(replacement) = own
+                        .iter()
+                        .find(|s| s.file_name() == source.file_name() && **s != source)
+                    {
+                        overridden.push(OverriddenSource {
+                            source: replacement.clone(),
+                            overridden: source,
+                        });
+                    }
+                }
+                current = views
+                    .iter()
+                    .find(|v| v.project == project)
+                    .and_then(|v| v.extended.clone());
+            }
+        }
+        Ok(overridden)
+    }
+
+    /// Returns the `Project_Files` of an aggregate project.
+    pub fn project_files(&self) -> Result<Vec<PathBuf>, error::Error> {
+        self.path_attribute("project_files")
+    }
+
+    /// Returns the externals an aggregate project sets for its aggregated
+    /// projects with `for External (...) use ...`. With libgpr2 the names of
+    /// the externals come from the native evaluation of the project, which
+    /// fails with [`error::Error::NotReported`] if it cannot evaluate it.
+    pub fn aggregate_externals(&self) -> Result<BTreeMap<String, String>, error::Error> {
+        let root = self.tree.root_view();
+        let mut externals = BTreeMap::new();
+        for name in self.tree.attribute_indexes(root, None, "external")? {
+            let value = self.get_single_attribute_value(
+                "external",
+                self.tree
+                    .get_view_attribute(root, None, "external", Some(&name))?
+                    .value,
+            )?;
+            externals.insert(name, value);
+        }
+        Ok(externals)
+    }
+
+    /// Returns the projects aggregated by an aggregate or aggregate library
+    /// project in the order of its `Project_Files`.
+    pub fn aggregated(&self) -> Result<Vec<Aggregated>, error::Error> {
+        let mut externals: BTreeMap<String, String> = self
+            .tree
+            .context()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        externals.extend(self.context.clone());
+        externals.extend(self.aggregate_externals()?);
+        let mut views = Vec::new();
+        for id in self.tree.views() {
+            let view = self.tree.view(id)?;
+            if let Ok(path) = view.path.canonicalize() {
+                views.push((path, view));
+            }
+        }
+        let mut aggregated = Vec::new();
+        for file in self.project_files()? {
+            let file = file.canonicalize()?;
+            let Some((path, view)) = views.iter().find(|(path, _)| *path == file) else {
+                return Err(error::Error::ViewNotFound(error::Exception::new(
+                    2,
+                    "UnknownView",
+                    &format!(
+                        "{} of Project_Files in {} is not part of the project tree",
+                        file.display(),
+                        self.file.display()
+                    ),
+                )));
+            };
+            aggregated.push(Aggregated {
+                name: view.name.clone(),
+                project: path.clone(),
+                kind: ProjectKind::from_view(path, &view.kind)?,
+                externals: externals.clone(),
+            });
+        }
+        Ok(aggregated)
+    }
+
+    pub fn source_dirs(&self) -> Result<Vec<PathBuf>, error::Error> {
+        self.path_attribute("source_dirs")
+    }
+
+    pub fn languages(&self) -> Result<Vec<String>, error::Error> {
+        list!(self, "languages")
+    }
+
+    pub fn configuration(&self) -> &Configuration {
+        &self.config
+    }
+
+    /// Backend the project tree was loaded with.
+    pub fn backend(&self) -> Backend {
+        self.tree.backend()
+    }
+
+    pub fn target(&self) -> &str {
+        self.tree.target()
+    }
+
+    pub fn canonical_target(&self) -> &str {
+        self.tree.canonical_target()
+    }
+
+    /// Returns a single valued attribute of the configuration project the
+    /// tree has been loaded with, e.g. `("runtime_dir", Some("ada"))`.
+    pub fn config_attribute(
+        &self,
+        name: &str,
+        index: Option<&str>,
+    ) -> Result<String, error::Error> {
+        let view = self
+            .tree
+            .config_view()
+            .ok_or(error::Error::NoConfiguration)?;
+        self.get_single_attribute_value(
+            name,
+            self.tree.get_view_attribute(view, None, name, index)?.value,
+        )
+    }
+
+    /// Generates a Rust module with a guard that initializes the standalone
+    /// library on first use and a `finalize` function to finalize it.
+    pub fn elaboration_guard(&self) -> Result<String, error::Error> {
+        let standalone = single!(self, "library_standalone")?;
+        if !matches!(
+            standalone.to_lowercase().as_str(),
+            "standard" | "encapsulated"
+        ) {
+            return Err(error::Error::invalid_attribute(
                 &self.file,
-                "library_kind",
-                value,
-            )),
+                "library_standalone",
+                &standalone,
+            ));
         }
+        let auto_init = single!(self, "library_auto_init")?.eq_ignore_ascii_case("true");
+        Ok(elaboration::generate(&self.library_name()?, auto_init))
     }
 
-    pub fn source_dirs(&self) -> Result<Vec<String>, error::Error> {
-        list!(self, "source_dirs")
+    /// Writes the elaboration guard to `<dir>/<library_name>_elaboration.rs`
+    /// so it can be included with
+    /// `include!(concat!(env!("OUT_DIR"), "/<library_name>_elaboration.rs"))`.
+    pub fn write_elaboration_guard(&self, dir: &Path) -> Result<PathBuf, error::Error> {
+        let file = dir.join(format!("{}_elaboration.rs", self.library_name()?));
+        std::fs::write(&file, self.elaboration_guard()?)?;
+        Ok(file)
     }
 
-    pub fn gprbuild_args(&self) -> Result<Vec<String>, error::Error> {
-        Ok(vec![
-            String::from("-P"),
-            String::from(self.file.to_str().ok_or_else(|| {
-                error::Error::from_code(
-                    error::Code::UnknownError,
-                    "InvalidFile",
-                    "Failed to get path to project file",
-                )
-            })?),
-        ])
+    /// Generates a Rust module with `extern "C"` declarations and `#[repr(C)]`
+    /// types for all subprograms exported with convention C by the units of
+    /// the library interface.
+    pub fn bindings(&self) -> Result<String, error::Error> {
+        let units = list!(self, "library_interface")?;
+        let sources = self.tree.sources(self.tree.root_view())?;
+        bindgen::generate(
+            &self.library_name()?,
+            &units,
+            &self.naming(&units)?,
+            &sources,
+            &self.file,
+        )
+    }
+
+    /// Returns the naming scheme of the Ada sources of the root project,
+    /// including the spec files named explicitly for `units`.
+    fn naming(&self, units: &[String]) -> Result<bindgen::Naming, error::Error> {
+        let attribute = |name: &str, index: Option<&str>| -> Result<Option<String>, error::Error> {
+            match self
+                .tree
+                .get_view_attribute(self.tree.root_view(), Some("naming"), name, index)
+            {
+                Ok(attribute) => Ok(Some(
+                    self.get_single_attribute_value(name, attribute.value)?,
+                )),
+                Err(error::Error::AttributeNotFound(_)) => Ok(None),
+                Err(error) => Err(error),
+            }
+        };
+        let mut naming = bindgen::Naming::default();
+        if let Some(suffix) = attribute("spec_suffix", Some("ada"))? {
+            naming.spec_suffix = suffix;
+        }
+        if let Some(replacement) = attribute("dot_replacement", None)? {
+            naming.dot_replacement = replacement;
+        }
+        if let Some(casing) = attribute("casing", None)? {
+            naming.casing = match casing.to_lowercase().as_str() {
+                "uppercase" => bindgen::Casing::Uppercase,
+                "mixedcase" => bindgen::Casing::Mixedcase,
+                _ => bindgen::Casing::Lowercase,
+            };
+        }
+        for unit in units {
+            if let Some(file) = attribute("spec", Some(unit))? {
+                naming.specs.insert(unit.to_lowercase(), file);
+            }
+        }
+        Ok(naming)
+    }
+
+    /// Writes the bindings to `<dir>/<library_name>_bindings.rs`.
+    pub fn write_bindings(&self, dir: &Path) -> Result<PathBuf, error::Error> {
+        let file = dir.join(format!("{}_bindings.rs", self.library_name()?));
+        std::fs::write(&file, self.bindings()?)?;
+        Ok(file)
+    }
+
+    /// Generates an Ada package spec `package` that imports the `extern "C"`
+    /// functions and `#[repr(C)]` types of the Rust source file `source` and
+    /// adds it to the project sources. The spec is written to the `gpr-rust`
+    /// subdirectory of the object directory, which is added to the source
+    /// directories of the project tree when it is reloaded and built.
+    ///
+    /// Requires the `ada-bindings` feature.
+    #[cfg(feature = "ada-bindings")]
+    pub fn add_ada_bindings(
+        &mut self,
+        source: &Path,
+        package: &str,
+    ) -> Result<PathBuf, error::Error> {
+        let code = adagen::generate(&std::fs::read_to_string(source)?, source, package)?;
+        let dir = self.object_dir()?.join(SRC_SUBDIRS);
+        std::fs::create_dir_all(&dir)?;
+        let file = dir.join(adagen::spec_file(package));
+        std::fs::write(&file, code)?;
+        if self.tree.src_subdirs().is_none() {
+            self.tree = backend::Tree::load(
+                self.tree.backend(),
+                &self.file,
+                &self.context,
+                self.config.file(),
+                Some(SRC_SUBDIRS),
+            )?;
+        }
+        Ok(file)
+    }
+
+    /// Returns the `adalib` directory of the Ada runtime the project is built
+    /// with.
+    pub fn runtime_dir(&self) -> Result<PathBuf, error::Error> {
+        Ok(PathBuf::from(self.config_attribute("runtime_dir", Some("ada"))?).join("adalib"))
+    }
+
+    /// Checks whether any library of the project tree depends on the tasking
+    /// runtime (libgnarl). This information is taken from the ALI files in the
+    /// library directories and therefore only available after the project has
+    /// been built.
+    pub fn uses_tasking(&self) -> Result<bool, error::Error> {
+        for library in self.libraries()? {
+            if link::uses_tasking(&library.dir)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Libraries linked by [`Project::link_directives`], the aggregated
+    /// libraries for an aggregate project and the library itself otherwise.
+    pub(crate) fn linked_libraries(&self) -> Result<Vec<Library>, error::Error> {
+        if self.kind()? == ProjectKind::Aggregate {
+            self.libraries()
+        } else {
+            Ok(vec![Library {
+                name: self.library_name()?,
+                dir: self.library_dir()?,
+                kind: self.library_kind()?,
+                project: self.file.clone(),
+            }])
+        }
+    }
+
+    /// Returns the cargo directives required to link the library. For an
+    /// aggregate project the libraries of all aggregated library projects
+    /// are linked.
+    pub fn link_directives(&self, options: &LinkOptions) -> Result<Vec<Directive>, error::Error> {
+        let adalib = match options.runtime_kind() {
+            Some(_) => Some(self.runtime_dir()?),
+            None => None,
+        };
+        Ok(link::directives(
+            &self.linked_libraries()?,
+            &self.libraries()?,
+            adalib.as_deref(),
+            self.uses_tasking()?,
+            options,
+        ))
+    }
+
+    /// Copies the dynamic libraries of the project tree, and the dynamic GNAT
+    /// runtime if `options` link it, next to the binaries of the current
+    /// cargo profile, as required by [`Rpath::Origin`]. This only works
+    /// inside a build script.
+    pub fn copy_dynamic_libraries(&self, options: &LinkOptions) -> Result<(), error::Error> {
+        for library in self.libraries()? {
+            if library.kind == LibraryKind::Dynamic {
+                link::copy_shared_objects(&library.dir, &[&library.name])?;
+            }
+        }
+        if options.runtime_kind() == Some(&LibraryKind::Dynamic) {
+            let names: &[&str] = if self.uses_tasking()? {
+                &["gnarl", "gnat"]
+            } else {
+                &["gnat"]
+            };
+            link::copy_shared_objects(&self.runtime_dir()?, names)?;
+        }
+        Ok(())
+    }
+
+    /// Returns all scenario variables referenced by the project tree together
+    /// with the value they have in this project.
+    pub fn scenario_variables(&self) -> Result<Vec<ScenarioVariable>, error::Error> {
+        let context = self.tree.context();
+        let mut variables = scenario::scenario_variables(&self.file)?;
+        for variable in &mut variables {
+            variable.value = context
+                .iter()
+                .chain(self.context.iter())
+                .find(|(name, _)| name.eq_ignore_ascii_case(&variable.name))
+                .map(|(_, value)| value.clone())
+                .or_else(|| variable.default.clone());
+        }
+        Ok(variables)
+    }
+
+    /// Loads the project once for every legal combination of its typed
+    /// scenario variables, see [`Combinations::new`].
+    pub fn combinations(file: &Path, only: Option<&[&str]>) -> Result<Combinations, error::Error> {
+        Combinations::new(file, only)
+    }
+
+    /// Arguments of gprbuild to build the project as it has been loaded.
+    /// Paths are passed on unchanged, even if they are not valid UTF-8.
+    pub fn gprbuild_args(&self) -> Result<Vec<OsString>, error::Error> {
+        let mut args = vec![OsString::from("-P"), self.file.clone().into_os_string()];
+        if let Configuration::File(config) = &self.config {
+            let mut arg = OsString::from("--config=");
+            arg.push(config);
+            args.push(arg);
+        }
+        if let Some(src_subdirs) = self.tree.src_subdirs() {
+            args.push(OsString::from(format!("--src-subdirs={src_subdirs}")));
+        }
+        args.extend(
+            self.context
+                .iter()
+                .map(|(k, v)| OsString::from(format!("-X{k}={v}"))),
+        );
+        Ok(args)
     }
 }
 
@@ -180,7 +786,11 @@ mod tests {
     fn test_source_dirs() {
         initialize();
         let prj = prj!("testdata/testlib.gpr");
-        assert_eq!(prj.source_dirs().unwrap(), vec!["src", "src2"]);
+        let base = Path::new("testdata").canonicalize().unwrap();
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![base.join("src"), base.join("src2")]
+        );
     }
 
     #[test]
@@ -218,6 +828,592 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scenario() {
+        initialize();
+        let scenario = Scenario::new().feature("tls", "ENABLE_TLS", "yes", Some("no"));
+        let prj =
+            Project::load_with_scenario(Path::new("testdata/scenario.gpr"), &scenario).unwrap();
+        assert_eq!(prj.library_name().unwrap(), "scenario");
+        assert!(prj
+            .gprbuild_args()
+            .unwrap()
+            .contains(&OsString::from("-XENABLE_TLS=no")));
+    }
+
+    #[test]
+    fn test_scenario_variables() {
+        initialize();
+        let scenario = Scenario::new().external("SCENARIO_MODE", "release");
+        let prj =
+            Project::load_with_scenario(Path::new("testdata/scenario.gpr"), &scenario).unwrap();
+        let variables = prj.scenario_variables().unwrap();
+        assert_eq!(variables[0].value.as_deref(), Some("no"));
+        assert_eq!(variables[1].value.as_deref(), Some("release"));
+    }
+
+    #[test]
+    fn test_combinations() {
+        initialize();
+        let combinations = Project::combinations(Path::new("testdata/scenario.gpr"), None).unwrap();
+        let mut names = Vec::new();
+        for combination in combinations {
+            names.push(combination.project.unwrap().library_name().unwrap());
+        }
+        assert_eq!(
+            names,
+            vec!["scenario_tls", "scenario_tls", "scenario", "scenario"]
+        );
+        assert!(
+            Project::combinations(Path::new("testdata/scenario.gpr"), None)
+                .unwrap()
+                .failures()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_invalid_scenario() {
+        initialize();
+        let scenario = Scenario::new().external("ENABLE_TLS", "maybe");
+        assert!(matches!(
+            Project::load_with_scenario(Path::new("testdata/scenario.gpr"), &scenario),
+            Err(error::Error::InvalidScenarioValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_config() {
+        initialize();
+        let output = std::env::temp_dir().join("gpr_rust_test_config.cgpr");
+        let config = Configuration::cached(&output, &["Ada"], None, None).unwrap();
+        assert_eq!(
+            Configuration::cached(&output, &["Ada"], None, None).unwrap(),
+            config
+        );
+        let file = config.file().unwrap().to_path_buf();
+        assert!(file.is_file());
+        assert_ne!(file, output);
+        let prj =
+            Project::load_with_config(Path::new("testdata/testlib.gpr"), &Scenario::new(), &config)
+                .unwrap();
+        assert_eq!(prj.configuration(), &config);
+        assert!(prj
+            .gprbuild_args()
+            .unwrap()
+            .contains(&OsString::from(format!("--config={}", file.display()))));
+        assert_eq!(prj.config_attribute("target", None).unwrap(), prj.target());
+    }
+
+    #[test]
+    fn test_find_ada_compiler() {
+        let compiler = find_ada_compiler(None, None).unwrap();
+        assert!(compiler.native);
+        assert!(compiler.path.join("gnatmake").exists());
+        assert!(matches!(
+            find_ada_compiler(Some("invalid-target"), None),
+            Err(error::Error::NoCompiler { .. })
+        ));
+    }
+
+    #[test]
+    fn test_link_runtime() {
+        initialize();
+        let prj = prj!("testdata/testlib.gpr");
+        let runtime_dir = prj.runtime_dir().unwrap();
+        assert!(runtime_dir.ends_with("adalib"));
+        let directives = prj
+            .link_directives(&LinkOptions::new().runtime(LibraryKind::Static))
+            .unwrap();
+        assert_eq!(
+            directives[1],
+            Directive::LinkLib(LibraryKind::Static, String::from("test"))
+        );
+        assert!(directives.contains(&Directive::LinkSearch(runtime_dir)));
+        assert_eq!(
+            directives.last().unwrap(),
+            &Directive::LinkLib(LibraryKind::Static, String::from("gnat"))
+        );
+        assert!(!directives.contains(&Directive::LinkLib(
+            LibraryKind::Static,
+            String::from("gnarl")
+        )));
+    }
+
+    #[test]
+    fn test_rpath() {
+        initialize();
+        let prj = prj!("testdata/test2.gpr");
+        let libraries = prj.libraries().unwrap();
+        assert_eq!(libraries.len(), 1);
+        assert_eq!(libraries[0].name, "test2");
+        assert_eq!(libraries[0].kind, LibraryKind::Dynamic);
+        assert_eq!(libraries[0].dir, prj.library_dir().unwrap());
+        let directives = prj
+            .link_directives(&LinkOptions::new().rpath(Rpath::Absolute))
+            .unwrap();
+        assert!(directives.contains(&Directive::LinkArg(format!(
+            "-Wl,-rpath,{}",
+            prj.library_dir().unwrap().display()
+        ))));
+    }
+
+    #[test]
+    fn test_elaboration_guard() {
+        initialize();
+        let prj = prj!("testdata/test2.gpr");
+        let code = prj.elaboration_guard().unwrap();
+        assert!(code.contains("fn test2final();"));
+        let file = prj.write_elaboration_guard(&std::env::temp_dir()).unwrap();
+        assert!(file.ends_with("test2_elaboration.rs"));
+        assert!(matches!(
+            prj!("testdata/testlib.gpr").elaboration_guard(),
+            Err(error::Error::InvalidAttribute { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bindings() {
+        initialize();
+        let prj = prj!("testdata/test2.gpr");
+        assert!(prj.bindings().unwrap().contains(
+            "pub fn test2_add(a: core::ffi::c_int, b: core::ffi::c_int) -> core::ffi::c_int;"
+        ));
+        let file = prj.write_bindings(&std::env::temp_dir()).unwrap();
+        assert!(file.ends_with("test2_bindings.rs"));
+    }
+
+    #[test]
+    #[cfg(feature = "ada-bindings")]
+    fn test_ada_bindings() {
+        initialize();
+        // The spec is written to the object directory, so the project is
+        // copied to keep it out of the source tree.
+        let dir = std::env::temp_dir().join(format!("gpr_rust_bindings_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let src = Path::new("testdata").canonicalize().unwrap();
+        let file = dir.join("testlib.gpr");
+        std::fs::write(
+            &file,
+            std::fs::read_to_string("testdata/testlib.gpr")
+                .unwrap()
+                .replace(
+                    "(\"src\", \"src2\")",
+                    &format!(
+                        "(\"{}\", \"{}\")",
+                        src.join("src").display(),
+                        src.join("src2").display()
+                    ),
+                ),
+        )
+        .unwrap();
+        let mut prj = Project::load(&file).unwrap();
+        let file = prj
+            .add_ada_bindings(Path::new("testdata/rust/exports.rs"), "Rust_Exports")
+            .unwrap();
+        assert_eq!(
+            file,
+            prj.object_dir().unwrap().join("gpr-rust/rust_exports.ads")
+        );
+        assert!(std::fs::read_to_string(&file)
+            .unwrap()
+            .contains("External_Name => \"rust_exports_add\""));
+        assert!(prj
+            .gprbuild_args()
+            .unwrap()
+            .contains(&OsString::from("--src-subdirs=gpr-rust")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rust_library() {
+        initialize();
+        let dir = std::env::temp_dir().join("gpr_rust_test_rust_library");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = RustLibrary::new("rust-math", &dir, LibraryKind::Dynamic)
+            .linker_options(&["-lutil"])
+            .write(&dir)
+            .unwrap();
+        assert!(file.ends_with("rust_math.gpr"));
+        let prj = Project::load(&file).unwrap();
+        assert!(prj.name().unwrap().eq_ignore_ascii_case("rust_math"));
+        assert_eq!(prj.library_name().unwrap(), "rust_math");
+        assert_eq!(prj.library_kind().unwrap(), LibraryKind::Dynamic);
+        assert_eq!(prj.library_dir().unwrap(), dir);
+    }
+
+    #[test]
+    fn test_project_file() {
+        initialize();
+        let file = ProjectFile::new("Generated", ProjectKind::Library)
+            .declare(Declaration::type_declaration(
+                "Kind_Type",
+                &["static", "relocatable"],
+            ))
+            .declare(Declaration::typed_variable(
+                "Kind",
+                "Kind_Type",
+                Expression::External {
+                    name: String::from("GENERATED_KIND"),
+                    default: Some(String::from("static")),
+                },
+            ))
+            .attribute("Source_Dirs", Expression::List(Vec::new()))
+            .attribute("Library_Name", "generated")
+            .attribute("Library_Dir", "lib")
+            .case(
+                Case::new("Kind")
+                    .when(
+                        &["static"],
+                        vec![Declaration::attribute("Library_Kind", "static")],
+                    )
+                    .when(
+                        &[],
+                        vec![Declaration::attribute("Library_Kind", "relocatable")],
+                    ),
+            )
+            .write(&std::env::temp_dir())
+            .unwrap();
+        let prj = Project::load(&file).unwrap();
+        assert_eq!(prj.library_name().unwrap(), "generated");
+        assert_eq!(prj.library_kind().unwrap(), LibraryKind::Static);
+    }
+
+    #[test]
+    fn test_aggregate() {
+        initialize();
+        let prj = prj!("testdata/aggregate.gpr");
+        assert_eq!(prj.kind().unwrap(), ProjectKind::Aggregate);
+        assert!(prj.library_name().is_err());
+        let aggregated = prj.aggregated().unwrap();
+        assert_eq!(aggregated.len(), 2);
+        assert!(aggregated[0].name.eq_ignore_ascii_case("testlib"));
+        assert_eq!(aggregated[1].kind, ProjectKind::Library);
+        assert_eq!(aggregated[1].externals["AGGREGATE_MODE"], "fast");
+        let directives = prj.link_directives(&LinkOptions::new()).unwrap();
+        assert!(directives.contains(&Directive::LinkLib(
+            LibraryKind::Static,
+            String::from("test")
+        )));
+        assert!(directives.contains(&Directive::LinkLib(
+            LibraryKind::Dynamic,
+            String::from("test2")
+        )));
+    }
+
+    #[test]
+    fn test_aggregate_library() {
+        initialize();
+        let prj = prj!("testdata/aggregate_lib.gpr");
+        assert_eq!(prj.kind().unwrap(), ProjectKind::AggregateLibrary);
+        assert_eq!(prj.library_name().unwrap(), "aggregate");
+        assert_eq!(prj.aggregated().unwrap()[0].kind, ProjectKind::Standard);
+        assert_eq!(
+            prj.link_directives(&LinkOptions::new()).unwrap()[1],
+            Directive::LinkLib(LibraryKind::Static, String::from("aggregate"))
+        );
+    }
+
+    #[test]
+    fn test_project_set() {
+        initialize();
+        let dir = std::env::temp_dir().join(format!("gpr_rust_set_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let set = ProjectSet::new(
+            &[
+                Path::new("testdata/testlib.gpr"),
+                Path::new("testdata/test2.gpr"),
+            ],
+            &dir,
+        )
+        .unwrap();
+        assert_eq!(set.aggregate().kind().unwrap(), ProjectKind::Aggregate);
+        assert_eq!(set.members().len(), 2);
+        assert_eq!(set.aggregate().aggregated().unwrap().len(), 2);
+        let libraries = set.libraries().unwrap();
+        assert_eq!(libraries.len(), 2);
+        assert!(set.gprbuild_args().unwrap().contains(
+            &dir.canonicalize()
+                .unwrap()
+                .join("gpr_rust_set.gpr")
+                .into_os_string()
+        ));
+        let directives = set.link_directives(&LinkOptions::new()).unwrap();
+        assert_eq!(directives.len(), 3);
+    }
+
+    #[test]
+    fn test_extension() {
+        initialize();
+        let prj = prj!("testdata/extending.gpr");
+        let views = prj.views().unwrap();
+        let plain = Path::new("testdata/plain.gpr").canonicalize().unwrap();
+        assert_eq!(views[0].extended.as_ref(), Some(&plain));
+        assert!(!views[0].extends_all);
+        let extended = views.iter().find(|v| v.project == plain).unwrap();
+        assert_eq!(extended.extending.as_ref(), Some(&views[0].project));
+        assert_eq!(
+            prj.attribute_project(None, "object_dir").unwrap(),
+            Some(plain)
+        );
+        let overridden = prj.overridden_sources().unwrap();
+        assert_eq!(overridden.len(), 1);
+        assert!(overridden[0].source.ends_with("extending/src/test2.adb"));
+        assert!(overridden[0].overridden.ends_with("testdata/src/test2.adb"));
+    }
+
+    #[test]
+    fn test_path_attributes() {
+        initialize();
+        let prj = prj!("testdata/paths/paths.gpr");
+        let base = Path::new("testdata").canonicalize().unwrap();
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![
+                base.join("src"),
+                base.join("ali"),
+                base.join("ali/sequential"),
+                base.join("ali/tasking"),
+            ]
+        );
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/paths"));
+        assert_eq!(
+            prj.path_attribute("exec_dir").unwrap(),
+            vec![PathBuf::from("/tmp")]
+        );
+        let prj = prj!("testdata/extending.gpr");
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/plain"));
+        let prj = prj!("testdata/paths/reuse.gpr");
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![base.join("src"), base.join("ali")]
+        );
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/plain/reuse"));
+    }
+
+    /// Path attributes of a libgpr2 tree are resolved by gpr2, so they do
+    /// not need the native evaluation of the project, which fails here as
+    /// the project file is replaced after loading.
+    #[test]
+    fn test_libgpr2_path_attributes() {
+        initialize();
+        let dir = std::env::temp_dir()
+            .canonicalize()
+            .unwrap()
+            .join(format!("gpr_rust_paths_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/nested")).unwrap();
+        let file = dir.join("paths.gpr");
+        std::fs::write(
+            &file,
+            "project Paths is\n   for Source_Dirs use (\"src/**\");\n   \
+             for Object_Dir  use \"obj\";\nend Paths;\n",
+        )
+        .unwrap();
+        let prj = Project::load_with_backend(
+            &file,
+            &Scenario::new(),
+            &Configuration::Auto,
+            Backend::Libgpr2,
+        )
+        .unwrap();
+        std::fs::write(&file, "project Paths is").unwrap();
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![dir.join("src"), dir.join("src/nested")]
+        );
+        assert_eq!(prj.object_dir().unwrap(), dir.join("obj"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn load_native(file: &str) -> Project {
+        Project::load_with_backend(
+            Path::new(file),
+            &Scenario::new(),
+            &Configuration::Auto,
+            Backend::Native,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_native_backend() {
+        let prj = load_native("testdata/testlib.gpr");
+        let base = Path::new("testdata").canonicalize().unwrap();
+        assert_eq!(prj.backend(), Backend::Native);
+        assert_eq!(prj.name().unwrap(), "testlib");
+        assert_eq!(prj.kind().unwrap(), ProjectKind::Library);
+        assert_eq!(prj.library_name().unwrap(), "test");
+        assert_eq!(prj.library_kind().unwrap(), LibraryKind::Static);
+        assert_eq!(prj.library_dir().unwrap(), base.join("lib"));
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![base.join("src"), base.join("src2")]
+        );
+        let prj = load_native("testdata/extending.gpr");
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/plain"));
+        assert_eq!(prj.views().unwrap().len(), 2);
+        let prj = load_native("testdata/test2.gpr");
+        assert!(prj
+            .elaboration_guard()
+            .unwrap()
+            .contains("fn test2final();"));
+        assert!(matches!(
+            load_native("testdata/testlib.gpr").elaboration_guard(),
+            Err(error::Error::InvalidAttribute { .. })
+        ));
+        let prj = load_native("testdata/aggregate.gpr");
+        assert_eq!(prj.aggregated().unwrap().len(), 2);
+        assert_eq!(prj.libraries().unwrap().len(), 2);
+        assert!(Project::load_with_backend(
+            Path::new("testdata/invalid.gpr"),
+            &Scenario::new(),
+            &Configuration::Auto,
+            Backend::Native,
+        )
+        .is_err());
+    }
+
+    /// Resolves path attributes that refer to other projects and ones
+    /// inherited from an extended project.
+    #[test]
+    fn test_native_path_values() {
+        let prj = load_native("testdata/paths/reuse.gpr");
+        let base = Path::new("testdata").canonicalize().unwrap();
+        assert_eq!(
+            prj.source_dirs().unwrap(),
+            vec![base.join("src"), base.join("ali")]
+        );
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/plain/reuse"));
+        let prj = load_native("testdata/extending.gpr");
+        assert_eq!(prj.object_dir().unwrap(), base.join("obj/plain"));
+    }
+
+    #[test]
+    fn test_native_extension() {
+        let prj = load_native("testdata/extending.gpr");
+        let extending = Path::new("testdata/extending.gpr").canonicalize().unwrap();
+        let plain = Path::new("testdata/plain.gpr").canonicalize().unwrap();
+        let views = prj.views().unwrap();
+        assert_eq!(views[0].extended.as_ref(), Some(&plain));
+        assert_eq!(views[1].extending.as_ref(), Some(&extending));
+        assert_eq!(
+            prj.attribute_project(None, "object_dir").unwrap(),
+            Some(plain)
+        );
+        assert_eq!(
+            prj.attribute_project(None, "source_dirs").unwrap(),
+            Some(extending.clone())
+        );
+        assert_eq!(prj.attribute_project(None, "exec_dir").unwrap(), None);
+        let overridden = prj.overridden_sources().unwrap();
+        assert_eq!(overridden.len(), 1);
+        assert!(overridden[0].source.ends_with("extending/src/test2.adb"));
+        assert!(overridden[0].overridden.ends_with("testdata/src/test2.adb"));
+        let prj = Project::load_with_backend(
+            Path::new("testdata/extending.gpr"),
+            &Scenario::new().external("EXTENDING_MODE", "release"),
+            &Configuration::Auto,
+            Backend::Native,
+        )
+        .unwrap();
+        assert_eq!(
+            prj.attribute_project(None, "object_dir").unwrap(),
+            Some(extending)
+        );
+    }
+
+    #[test]
+    fn test_native_conformance() {
+        initialize();
+        let extending = Path::new("testdata/extending.gpr").canonicalize().unwrap();
+        let plain = Path::new("testdata/plain.gpr").canonicalize().unwrap();
+        for file in [
+            "testdata/testlib.gpr",
+            "testdata/test2.gpr",
+            "testdata/plain.gpr",
+            "testdata/extending.gpr",
+            "testdata/scenario.gpr",
+            "testdata/paths/paths.gpr",
+            "testdata/paths/reuse.gpr",
+            "testdata/aggregate.gpr",
+            "testdata/aggregate_lib.gpr",
+        ] {
+            let expected = prj!(file);
+            let native = load_native(file);
+            assert!(native
+                .name()
+                .unwrap()
+                .eq_ignore_ascii_case(&expected.name().unwrap()));
+            assert_eq!(native.kind().unwrap(), expected.kind().unwrap(), "{file}");
+            assert_eq!(
+                native.source_dirs().unwrap(),
+                expected.source_dirs().unwrap(),
+                "{file}"
+            );
+            assert_eq!(
+                native.object_dir().unwrap(),
+                expected.object_dir().unwrap(),
+                "{file}"
+            );
+            assert_eq!(
+                native.library_name().ok(),
+                expected.library_name().ok(),
+                "{file}"
+            );
+            assert_eq!(
+                native.library_dir().ok(),
+                expected.library_dir().ok(),
+                "{file}"
+            );
+            assert_eq!(
+                native.library_kind().ok(),
+                expected.library_kind().ok(),
+                "{file}"
+            );
+            assert_eq!(
+                native.libraries().unwrap(),
+                expected.libraries().unwrap(),
+                "{file}"
+            );
+            assert_eq!(
+                native.aggregated().ok(),
+                expected.aggregated().ok(),
+                "{file}"
+            );
+            assert_eq!(
+                native.elaboration_guard().ok(),
+                expected.elaboration_guard().ok(),
+                "{file}"
+            );
+            let views = |prj: &Project| {
+                let mut views: Vec<_> = prj
+                    .views()
+                    .unwrap()
+                    .into_iter()
+                    .map(|v| (v.project, v.kind))
+                    .collect();
+                views.sort_by(|a, b| a.0.cmp(&b.0));
+                views
+            };
+            assert_eq!(views(&native), views(&expected), "{file}");
+            // Both backends have to report the extension declared in the
+            // project files.
+            for prj in [&native, &expected] {
+                for view in prj.views().unwrap() {
+                    let (extended, extending) = if view.project == extending {
+                        (Some(plain.clone()), None)
+                    } else if view.project == plain && file == "testdata/extending.gpr" {
+                        (None, Some(extending.clone()))
+                    } else {
+                        (None, None)
+                    };
+                    assert_eq!(view.extended, extended, "{file}");
+                    assert_eq!(view.extending, extending, "{file}");
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_invalid_project() {
         initialize();
@@ -229,4 +1425,73 @@ mod tests {
         assert_eq!(format!("{}", LibraryKind::Dynamic), "dylib");
         assert_eq!(format!("{}", LibraryKind::Static), "static");
     }
+
+    #[test]
+    fn test_invalid_path() {
+        let error = Project::load_with_backend(
+            Path::new("testdata/plain\0.gpr"),
+            &Scenario::new(),
+            &Configuration::Auto,
+            Backend::Libgpr2,
+        )
+        .unwrap_err();
+        assert!(matches!(error, error::Error::InvalidPath { .. }));
+    }
+
+    /// Loads a copy of `plain.gpr` whose file name is not valid UTF-8.
+    #[cfg(unix)]
+    fn check_non_utf8_path(backend: Backend, name: &[u8]) {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let file = dir.join(OsStr::from_bytes(name));
+        std::fs::copy("testdata/plain.gpr", &file).unwrap();
+        let prj =
+            Project::load_with_backend(&file, &Scenario::new(), &Configuration::Auto, backend)
+                .unwrap();
+        assert_eq!(prj.name().unwrap(), "Plain");
+        assert_eq!(prj.object_dir().unwrap(), dir.join("obj/plain"));
+        assert_eq!(prj.views().unwrap()[0].project, file);
+        let args = prj.gprbuild_args().unwrap();
+        assert_eq!(args[1], file.as_os_str());
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path() {
+        check_non_utf8_path(Backend::Native, b"gpr_rust_test_\xe9.gpr");
+    }
+
+    /// Paths that are not valid UTF-8 reach libgpr2 and come back unchanged.
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path_libgpr2() {
+        initialize();
+        check_non_utf8_path(Backend::Libgpr2, b"gpr_rust_test_libgpr2_\xe9.gpr");
+    }
+
+    #[cfg(gpr2_stub)]
+    #[test]
+    fn test_stub() {
+        let error = Project::load_with_backend(
+            Path::new("testdata/testlib.gpr"),
+            &Scenario::new(),
+            &Configuration::Auto,
+            Backend::Libgpr2,
+        )
+        .unwrap_err();
+        assert!(matches!(error, error::Error::Unavailable));
+    }
+
+    #[cfg(feature = "static")]
+    #[test]
+    fn test_static_linking() {
+        let output = Command::new("ldd")
+            .arg(std::env::current_exe().unwrap())
+            .output()
+            .unwrap();
+        let libraries = String::from_utf8_lossy(&output.stdout);
+        assert!(!libraries.contains("libgpr2c"), "{libraries}");
+        assert!(!libraries.contains("libgnat"), "{libraries}");
+    }
 }
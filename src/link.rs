@@ -0,0 +1,338 @@
+use std::{
+    collections::HashSet,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{error, Library, LibraryKind};
+
+/// Instruction for cargo printed by a build script.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Directive {
+    LinkSearch(PathBuf),
+    LinkLib(LibraryKind, String),
+    LinkArg(String),
+    RerunIfChanged(PathBuf),
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Directive::LinkSearch(path) => {
+                write!(f, "cargo:rustc-link-search={}", path.display())
+            }
+            Directive::LinkLib(kind, name) => write!(f, "cargo:rustc-link-lib={kind}={name}"),
+            Directive::LinkArg(arg) => write!(f, "cargo:rustc-link-arg={arg}"),
+            Directive::RerunIfChanged(path) => {
+                write!(f, "cargo:rerun-if-changed={}", path.display())
+            }
+        }
+    }
+}
+
+/// Way the run-time search path of dynamic Ada libraries is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rpath {
+    /// Use the absolute paths of the library directories.
+    Absolute,
+    /// Use `$ORIGIN` as search path, the libraries have to be copied next to
+    /// the binaries with [`Project::copy_dynamic_libraries`].
+    ///
+    /// [`Project::copy_dynamic_libraries`]: super::Project::copy_dynamic_libraries
+    Origin,
+}
+
+/// Options controlling which link directives are generated for a project.
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    runtime: Option<LibraryKind>,
+    rpath: Option<Rpath>,
+}
+
+impl LinkOptions {
+    pub fn new() -> LinkOptions {
+        LinkOptions::default()
+    }
+
+    /// Also link the GNAT runtime (`libgnat` and, if required, `libgnarl`).
+    /// This is needed for libraries that are not standalone libraries and
+    /// therefore do not contain the runtime themselves.
+    pub fn runtime(mut self, kind: LibraryKind) -> LinkOptions {
+        self.runtime = Some(kind);
+        self
+    }
+
+    /// Set the run-time search path for all dynamic libraries that are
+    /// linked, so binaries can be run without setting `LD_LIBRARY_PATH`.
+    ///
+    /// The search path is passed with `cargo:rustc-link-arg`, which cargo only
+    /// applies to the binaries, tests and examples of the package whose build
+    /// script prints it. It does not reach crates depending on that package,
+    /// e.g. on a `-sys` crate, so these have to set the search path in their
+    /// own build script.
+    pub fn rpath(mut self, rpath: Rpath) -> LinkOptions {
+        self.rpath = Some(rpath);
+        self
+    }
+
+    pub fn runtime_kind(&self) -> Option<&LibraryKind> {
+        self.runtime.as_ref()
+    }
+
+    pub fn rpath_kind(&self) -> Option<&Rpath> {
+        self.rpath.as_ref()
+    }
+}
+
+/// Output directory of the current cargo profile (e.g. `target/debug`),
+/// derived from the `OUT_DIR` of the running build script.
+pub(crate) fn profile_dir() -> Result<PathBuf, error::Error> {
+    let out_dir = env::var_os("OUT_DIR").ok_or_else(|| error::Error::no_build_env("OUT_DIR"))?;
+    Ok(Path::new(&out_dir)
+        .ancestors()
+        .nth(3)
+        .ok_or_else(|| error::Error::InvalidOutDir {
+            path: Path::new(&out_dir).display().to_string(),
+        })?
+        .to_path_buf())
+}
+
+/// Directories of the current cargo profile binaries.
+fn binary_dirs() -> Result<Vec<PathBuf>, error::Error> {
+    let profile = profile_dir()?;
+    Ok(vec![
+        profile.join("deps"),
+        profile.join("examples"),
+        profile,
+    ])
+}
+
+/// Checks whether `file` is a shared object of the library `name`, i.e.
+/// `lib<name>.so`, `lib<name>.so.<version>` or `lib<name>-<version>.so`.
+fn is_shared_object(file: &str, name: &str) -> bool {
+    file.strip_prefix("lib")
+        .and_then(|f| f.strip_prefix(name))
+        .is_some_and(|rest| {
+            rest.starts_with(".so") || (rest.starts_with('-') && rest.contains(".so"))
+        })
+}
+
+/// Directives setting the run-time search path for the dynamic libraries
+/// located in `dir`.
+pub(crate) fn rpath_directives(rpath: &Rpath, dir: &Path) -> Vec<Directive> {
+    match rpath {
+        Rpath::Absolute => vec![Directive::LinkArg(format!("-Wl,-rpath,{}", dir.display()))],
+        Rpath::Origin => vec![Directive::LinkArg(String::from("-Wl,-rpath,$ORIGIN"))],
+    }
+}
+
+/// Copies the shared objects of the libraries `names` located in `dir` next
+/// to the binaries of the current cargo profile.
+pub(crate) fn copy_shared_objects(dir: &Path, names: &[&str]) -> Result<(), error::Error> {
+    let targets = binary_dirs()?;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        if !names.iter().any(|n| is_shared_object(file_name, n)) {
+            continue;
+        }
+        for target in &targets {
+            fs::create_dir_all(target)?;
+            fs::copy(entry.path(), target.join(file_name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Directives linking the libraries `linked` followed by the GNAT runtime in
+/// `adalib`, if `options` request it, so the runtime comes after all
+/// libraries depending on it. The run-time search path is set for the
+/// dynamic libraries among `libraries`.
+pub(crate) fn directives(
+    linked: &[Library],
+    libraries: &[Library],
+    adalib: Option<&Path>,
+    tasking: bool,
+    options: &LinkOptions,
+) -> Vec<Directive> {
+    let mut directives = Vec::new();
+    for library in linked {
+        directives.push(Directive::LinkSearch(library.dir.clone()));
+        directives.push(Directive::LinkLib(
+            library.kind.clone(),
+            library.name.clone(),
+        ));
+    }
+    if let (Some(kind), Some(adalib)) = (options.runtime_kind(), adalib) {
+        directives.extend(runtime_directives(adalib, kind, tasking));
+    }
+    if let Some(rpath) = options.rpath_kind() {
+        for library in libraries {
+            if library.kind == LibraryKind::Dynamic {
+                directives.extend(rpath_directives(rpath, &library.dir));
+            }
+        }
+        if let (Some(LibraryKind::Dynamic), Some(adalib)) = (options.runtime_kind(), adalib) {
+            directives.extend(rpath_directives(rpath, adalib));
+        }
+    }
+    dedup(directives)
+}
+
+/// Removes all but the first occurrence of each directive.
+pub(crate) fn dedup(directives: Vec<Directive>) -> Vec<Directive> {
+    let mut seen = HashSet::new();
+    directives
+        .into_iter()
+        .filter(|directive| seen.insert(directive.clone()))
+        .collect()
+}
+
+/// Runtime units implemented in libgnarl, identified by their file name.
+const TASKING_UNITS: &[&str] = &[
+    "a-dynpri", "a-interr", "a-reatim", "a-rttiev", "a-synbar", "a-sytaco", "a-tasatt", "a-taside",
+    "a-taster", "s-interr", "s-taprop", "s-tarest", "s-tasini", "s-taskin", "s-tasren", "s-tassta",
+    "s-tpobop", "s-tpoben", "s-tposen",
+];
+
+/// Checks whether any ALI file in `dir` depends on a unit of the tasking
+/// runtime.
+pub(crate) fn uses_tasking(dir: &Path) -> Result<bool, error::Error> {
+    if !dir.is_dir() {
+        return Ok(false);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|e| e != "ali") {
+            continue;
+        }
+        let ali = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
+        let tasking = ali
+            .lines()
+            .filter_map(|l| l.strip_prefix("D "))
+            .filter_map(|l| l.split_whitespace().next())
+            .any(|file| {
+                TASKING_UNITS
+                    .iter()
+                    .any(|unit| file.strip_prefix(unit).is_some_and(|e| e.starts_with('.')))
+            });
+        if tasking {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Directives linking the GNAT runtime located in `adalib`.
+pub(crate) fn runtime_directives(
+    adalib: &Path,
+    kind: &LibraryKind,
+    tasking: bool,
+) -> Vec<Directive> {
+    let mut directives = vec![Directive::LinkSearch(adalib.to_path_buf())];
+    if tasking {
+        directives.push(Directive::LinkLib(kind.clone(), String::from("gnarl")));
+    }
+    directives.push(Directive::LinkLib(kind.clone(), String::from("gnat")));
+    directives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directive_display() {
+        assert_eq!(
+            format!(
+                "{}",
+                Directive::LinkLib(LibraryKind::Static, String::from("gnat"))
+            ),
+            "cargo:rustc-link-lib=static=gnat"
+        );
+        assert_eq!(
+            format!("{}", Directive::LinkSearch(PathBuf::from("/opt/adalib"))),
+            "cargo:rustc-link-search=/opt/adalib"
+        );
+    }
+
+    #[test]
+    fn test_uses_tasking() {
+        assert!(uses_tasking(Path::new("testdata/ali/tasking")).unwrap());
+        assert!(!uses_tasking(Path::new("testdata/ali/sequential")).unwrap());
+        assert!(!uses_tasking(Path::new("testdata/ali/missing")).unwrap());
+    }
+
+    #[test]
+    fn test_is_shared_object() {
+        assert!(is_shared_object("libgnat.so", "gnat"));
+        assert!(is_shared_object("libgnat-14.so", "gnat"));
+        assert!(is_shared_object("libtest2.so.1", "test2"));
+        assert!(!is_shared_object("libgnat.a", "gnat"));
+        assert!(!is_shared_object("libgnarl.so", "gnat"));
+        assert!(!is_shared_object("libgnat_pic.a", "gnat"));
+    }
+
+    #[test]
+    fn test_rpath_absolute() {
+        assert_eq!(
+            rpath_directives(&Rpath::Absolute, Path::new("/opt/lib")),
+            vec![Directive::LinkArg(String::from("-Wl,-rpath,/opt/lib"))]
+        );
+    }
+
+    #[test]
+    fn test_dedup() {
+        let rpath = Directive::LinkArg(String::from("-Wl,-rpath,$ORIGIN"));
+        let search = Directive::LinkSearch(PathBuf::from("/lib"));
+        assert_eq!(
+            dedup(vec![
+                rpath.clone(),
+                search.clone(),
+                rpath.clone(),
+                search.clone()
+            ]),
+            vec![rpath, search]
+        );
+    }
+
+    #[test]
+    fn test_runtime_directives() {
+        let directives = runtime_directives(Path::new("/adalib"), &LibraryKind::Dynamic, true);
+        assert_eq!(
+            directives,
+            vec![
+                Directive::LinkSearch(PathBuf::from("/adalib")),
+                Directive::LinkLib(LibraryKind::Dynamic, String::from("gnarl")),
+                Directive::LinkLib(LibraryKind::Dynamic, String::from("gnat")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directives() {
+        let library = |name: &str| Library {
+            name: String::from(name),
+            dir: PathBuf::from(format!("/lib/{}", name)),
+            kind: LibraryKind::Static,
+            project: PathBuf::from(format!("/prj/{}.gpr", name)),
+        };
+        let linked = [library("first"), library("second")];
+        let options = LinkOptions::new().runtime(LibraryKind::Static);
+        assert_eq!(
+            directives(&linked, &linked, Some(Path::new("/adalib")), true, &options),
+            vec![
+                Directive::LinkSearch(PathBuf::from("/lib/first")),
+                Directive::LinkLib(LibraryKind::Static, String::from("first")),
+                Directive::LinkSearch(PathBuf::from("/lib/second")),
+                Directive::LinkLib(LibraryKind::Static, String::from("second")),
+                Directive::LinkSearch(PathBuf::from("/adalib")),
+                Directive::LinkLib(LibraryKind::Static, String::from("gnarl")),
+                Directive::LinkLib(LibraryKind::Static, String::from("gnat")),
+            ]
+        );
+    }
+}
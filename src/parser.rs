@@ -0,0 +1,507 @@
+use std::path::Path;
+
+use super::{
+    ast::{
+        Alternative, Expr, Extends, Index, Item, ItemKind, Literal, Name, ProjectFile, Qualifier,
+        Span, Term, With,
+    },
+    error,
+    lexer::{self, Token, TokenKind},
+};
+
+/// Recursive descent parser for GPR project files.
+struct Parser<'a> {
+    file: &'a Path,
+    tokens: Vec<Token<'a>>,
+    index: usize,
+    /// Length of the source, the position of the end of file.
+    len: usize,
+}
+
+type Result<T> = std::result::Result<T, error::Error>;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.index)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token<'a>> {
+        self.tokens.get(self.index + n)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T> {
+        let (line, column) = match self.peek().or_else(|| self.tokens.last()) {
+            Some(token) => (token.line, token.column),
+            None => (1, 1),
+        };
+        Err(error::Error::syntax(self.file, line, column, message))
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => format!("\"{}\"", token.text),
+            None => String::from("end of file"),
+        }
+    }
+
+    /// Span from the token at `start` to the previous token.
+    fn span(&self, start: usize) -> Span {
+        let first = self.tokens.get(start);
+        let last = self.index.checked_sub(1).and_then(|i| self.tokens.get(i));
+        match first {
+            Some(first) => Span {
+                start: first.offset,
+                end: last.map_or(first.offset, |t| t.offset + t.text.len()),
+                line: first.line,
+                column: first.column,
+            },
+            None => Span {
+                start: self.len,
+                end: self.len,
+                ..Span::default()
+            },
+        }
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_keyword(keyword))
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|t| t.kind == kind)
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn accept(&mut self, kind: TokenKind) -> bool {
+        let found = self.at(kind);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.accept_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(&format!("expected \"{keyword}\", found {}", self.found()))
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, what: &str) -> Result<()> {
+        if self.accept(kind) {
+            Ok(())
+        } else {
+            self.error(&format!("expected {what}, found {}", self.found()))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Identifier => {
+                let text = token.text.to_owned();
+                self.index += 1;
+                Ok(text)
+            }
+            _ => self.error(&format!("expected identifier, found {}", self.found())),
+        }
+    }
+
+    fn name(&mut self) -> Result<Name> {
+        let start = self.index;
+        let mut parts = vec![self.identifier()?];
+        while self.at(TokenKind::Dot) {
+            self.index += 1;
+            parts.push(self.identifier()?);
+        }
+        Ok(Name {
+            parts,
+            span: self.span(start),
+        })
+    }
+
+    fn literal(&mut self) -> Result<Literal> {
+        let start = self.index;
+        match self.peek().and_then(Token::string_value) {
+            Some(value) => {
+                self.index += 1;
+                Ok(Literal {
+                    value,
+                    span: self.span(start),
+                })
+            }
+            None => self.error(&format!("expected string literal, found {}", self.found())),
+        }
+    }
+
+    fn semicolon(&mut self) -> Result<()> {
+        self.expect(TokenKind::Semicolon, "\";\"")
+    }
+
+    fn project_file(&mut self) -> Result<ProjectFile> {
+        let start = self.index;
+        let mut withs = Vec::new();
+        while self.at_keyword("with") || self.at_keyword("limited") {
+            let with_start = self.index;
+            let limited = self.accept_keyword("limited");
+            self.expect_keyword("with")?;
+            let mut paths = vec![self.literal()?];
+            while self.accept(TokenKind::Comma) {
+                paths.push(self.literal()?);
+            }
+            self.semicolon()?;
+            withs.push(With {
+                limited,
+                paths,
+                span: self.span(with_start),
+            });
+        }
+        let qualifier = self.qualifier()?;
+        self.expect_keyword("project")?;
+        let name = self.name()?;
+        let extends = if self.at_keyword("extends") {
+            let extends_start = self.index;
+            self.index += 1;
+            let all = self.accept_keyword("all");
+            let path = self.literal()?;
+            Some(Extends {
+                all,
+                path,
+                span: self.span(extends_start),
+            })
+        } else {
+            None
+        };
+        self.expect_keyword("is")?;
+        let items = self.items(&["end"])?;
+        self.end(&name)?;
+        if self.peek().is_some() {
+            return self.error(&format!("unexpected {} after end of project", self.found()));
+        }
+        Ok(ProjectFile {
+            withs,
+            qualifier,
+            name,
+            extends,
+            items,
+            span: self.span(start),
+        })
+    }
+
+    fn qualifier(&mut self) -> Result<Option<Qualifier>> {
+        let qualifier = match self.peek() {
+            Some(t) if t.is_keyword("abstract") => Qualifier::Abstract,
+            Some(t) if t.is_keyword("standard") => Qualifier::Standard,
+            Some(t) if t.is_keyword("library") => Qualifier::Library,
+            Some(t) if t.is_keyword("configuration") => Qualifier::Configuration,
+            Some(t) if t.is_keyword("aggregate") => {
+                if self.peek_nth(1).is_some_and(|t| t.is_keyword("library")) {
+                    self.index += 1;
+                    Qualifier::AggregateLibrary
+                } else {
+                    Qualifier::Aggregate
+                }
+            }
+            _ => return Ok(None),
+        };
+        self.index += 1;
+        Ok(Some(qualifier))
+    }
+
+    /// Parses `end <name>;`.
+    fn end(&mut self, name: &Name) -> Result<()> {
+        self.expect_keyword("end")?;
+        let end = self.name()?;
+        if !end.to_string().eq_ignore_ascii_case(&name.to_string()) {
+            return self.error(&format!("\"end {name};\" expected, found \"end {end};\""));
+        }
+        self.semicolon()
+    }
+
+    /// Parses declarative items until one of the keywords in `until`.
+    fn items(&mut self, until: &[&str]) -> Result<Vec<Item>> {
+        let mut items = Vec::new();
+        loop {
+            if self.peek().is_none() || until.iter().any(|k| self.at_keyword(k)) {
+                return Ok(items);
+            }
+            items.push(self.item()?);
+        }
+    }
+
+    fn item(&mut self) -> Result<Item> {
+        let start = self.index;
+        let kind = if self.accept_keyword("for") {
+            let name = self.name()?;
+            let index = self.index_opt()?;
+            self.expect_keyword("use")?;
+            let value = self.expr()?;
+            self.semicolon()?;
+            ItemKind::Attribute { name, index, value }
+        } else if self.accept_keyword("type") {
+            let name = self.name()?;
+            self.expect_keyword("is")?;
+            self.expect(TokenKind::LeftParen, "\"(\"")?;
+            let mut values = vec![self.literal()?];
+            while self.accept(TokenKind::Comma) {
+                values.push(self.literal()?);
+            }
+            self.expect(TokenKind::RightParen, "\")\"")?;
+            self.semicolon()?;
+            ItemKind::Type { name, values }
+        } else if self.accept_keyword("package") {
+            let name = self.name()?;
+            if self.accept_keyword("renames") {
+                let renames = Some(self.name()?);
+                self.semicolon()?;
+                ItemKind::Package {
+                    name,
+                    extends: None,
+                    renames,
+                    items: Vec::new(),
+                }
+            } else {
+                let extends = if self.accept_keyword("extends") {
+                    Some(self.name()?)
+                } else {
+                    None
+                };
+                self.expect_keyword("is")?;
+                let items = self.items(&["end"])?;
+                self.end(&name)?;
+                ItemKind::Package {
+                    name,
+                    extends,
+                    renames: None,
+                    items,
+                }
+            }
+        } else if self.accept_keyword("case") {
+            let variable = self.name()?;
+            self.expect_keyword("is")?;
+            let mut alternatives = Vec::new();
+            while self.at_keyword("when") {
+                let alternative_start = self.index;
+                self.index += 1;
+                let mut choices = Vec::new();
+                if !self.accept_keyword("others") {
+                    choices.push(self.literal()?);
+                    while self.accept(TokenKind::Pipe) {
+                        choices.push(self.literal()?);
+                    }
+                }
+                self.expect(TokenKind::Arrow, "\"=>\"")?;
+                let items = self.items(&["when", "end"])?;
+                alternatives.push(Alternative {
+                    choices,
+                    items,
+                    span: self.span(alternative_start),
+                });
+            }
+            self.expect_keyword("end")?;
+            self.expect_keyword("case")?;
+            self.semicolon()?;
+            ItemKind::Case {
+                variable,
+                alternatives,
+            }
+        } else if self.accept_keyword("null") {
+            self.semicolon()?;
+            ItemKind::Null
+        } else if self.at(TokenKind::Identifier) {
+            let name = self.name()?;
+            let type_name = if self.accept(TokenKind::Colon) {
+                Some(self.name()?)
+            } else {
+                None
+            };
+            self.expect(TokenKind::Assign, "\":=\"")?;
+            let value = self.expr()?;
+            self.semicolon()?;
+            ItemKind::Variable {
+                name,
+                type_name,
+                value,
+            }
+        } else {
+            return self.error(&format!("expected declaration, found {}", self.found()));
+        };
+        Ok(Item {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    /// Parses an optional attribute index `("value")` or `(others)`.
+    fn index_opt(&mut self) -> Result<Option<Index>> {
+        if !self.accept(TokenKind::LeftParen) {
+            return Ok(None);
+        }
+        let index = if self.accept_keyword("others") {
+            Index::Others
+        } else {
+            Index::Value(self.literal()?)
+        };
+        if self.accept_keyword("at") {
+            self.expect(TokenKind::Number, "number")?;
+        }
+        self.expect(TokenKind::RightParen, "\")\"")?;
+        Ok(Some(index))
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let start = self.index;
+        let mut terms = vec![self.term()?];
+        while self.accept(TokenKind::Ampersand) {
+            terms.push(self.term()?);
+        }
+        Ok(Expr {
+            terms,
+            span: self.span(start),
+        })
+    }
+
+    fn term(&mut self) -> Result<Term> {
+        let start = self.index;
+        if self.at(TokenKind::String) {
+            return Ok(Term::String(self.literal()?));
+        }
+        if self.accept(TokenKind::LeftParen) {
+            let mut values = Vec::new();
+            if !self.accept(TokenKind::RightParen) {
+                values.push(self.expr()?);
+                while self.accept(TokenKind::Comma) {
+                    values.push(self.expr()?);
+                }
+                self.expect(TokenKind::RightParen, "\")\"")?;
+            }
+            return Ok(Term::List(values, self.span(start)));
+        }
+        if !self.at(TokenKind::Identifier) {
+            return self.error(&format!("expected expression, found {}", self.found()));
+        }
+        let name = self.name()?;
+        if self.accept(TokenKind::Apostrophe) {
+            let attribute = self.name()?;
+            let index = self.index_opt()?;
+            return Ok(Term::Attribute {
+                prefix: name,
+                name: attribute,
+                index,
+            });
+        }
+        if self.at(TokenKind::LeftParen) {
+            self.index += 1;
+            let mut arguments = vec![self.expr()?];
+            while self.accept(TokenKind::Comma) {
+                arguments.push(self.expr()?);
+            }
+            self.expect(TokenKind::RightParen, "\")\"")?;
+            return Ok(Term::Call {
+                function: name,
+                arguments,
+            });
+        }
+        Ok(Term::Variable(name))
+    }
+}
+
+/// Parses the GPR source text of the project file `file`.
+pub(crate) fn parse(source: &str, file: &Path) -> Result<ProjectFile> {
+    let tokens: Vec<Token> = lexer::tokenize(source)
+        .into_iter()
+        .filter(|t| !t.is_trivia())
+        .collect();
+    if let Some(token) = tokens.iter().find(|t| t.kind == TokenKind::Unknown) {
+        return Err(error::Error::syntax(
+            file,
+            token.line,
+            token.column,
+            &format!("invalid token \"{}\"", token.text),
+        ));
+    }
+    Parser {
+        file,
+        tokens,
+        index: 0,
+        len: source.len(),
+    }
+    .project_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(source: &str) -> Result<ProjectFile> {
+        parse(source, Path::new("test.gpr"))
+    }
+
+    #[test]
+    fn test_parse() {
+        let source = std::fs::read_to_string("testdata/scenario.gpr").unwrap();
+        let project = parse(&source, Path::new("testdata/scenario.gpr")).unwrap();
+        assert_eq!(project.name.to_string(), "Scenario");
+        assert_eq!(project.withs[0].paths[0].value, "scenario_common");
+        assert_eq!(project.items.len(), 9);
+        let ItemKind::Case { alternatives, .. } = &project.items[5].kind else {
+            panic!("expected case statement");
+        };
+        assert_eq!(alternatives[0].choices[0].value, "yes");
+        let ItemKind::Variable {
+            type_name, value, ..
+        } = &project.items[2].kind
+        else {
+            panic!("expected variable");
+        };
+        assert_eq!(
+            type_name.as_ref().unwrap().to_string(),
+            "Scenario_Common.Mode_Type"
+        );
+        assert_eq!(
+            &source[value.span.start..value.span.end],
+            "external (\"SCENARIO_MODE\", \"debug\")"
+        );
+        assert_eq!(project.items[2].span.line, 7);
+    }
+
+    #[test]
+    fn test_qualifiers() {
+        let project = parse_str(
+            "aggregate library project A extends all \"b.gpr\" is\n\
+                package Builder renames B.Builder;\n\
+                for Switches (others) use Common'Switches (\"Ada\") & (\"-g\");\n\
+                null;\n\
+             end A;",
+        )
+        .unwrap();
+        assert_eq!(project.qualifier, Some(Qualifier::AggregateLibrary));
+        assert!(project.extends.as_ref().unwrap().all);
+        assert!(matches!(
+            &project.items[1].kind,
+            ItemKind::Attribute {
+                index: Some(Index::Others),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = parse_str("project P is\n   for X use \"a\"\nend P;").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "test.gpr:3:1: expected \";\", found \"end\""
+        );
+        assert!(parse_str("project P is\nend Q;").is_err());
+        assert!(parse_str("project P is\nend P;\nend P;").is_err());
+        assert!(parse_str("project P is\n   V := \"a\nend P;").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Resolves `.` and `..` components. The longest existing prefix of an
+/// absolute path is canonicalized, so `..` after a symbolic link refers to
+/// the parent of its target. The rest is normalized lexically, as
+/// directories like the object directory may not exist yet.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        let components: Vec<Component> = path.components().collect();
+        for end in (1..=components.len()).rev() {
+            let prefix: PathBuf = components[..end].iter().collect();
+            if let Ok(canonical) = prefix.canonicalize() {
+                let rest: PathBuf = components[end..].iter().collect();
+                return lexical(&canonical.join(rest));
+            }
+        }
+    }
+    lexical(path)
+}
+
+/// Removes `.` and `..` components without accessing the file system.
+fn lexical(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) {
+                    normalized.pop();
+                } else if !normalized.has_root() {
+                    normalized.push("..");
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Appends the subdirectories of `dir` to `dirs`. Symbolic links are
+/// followed, but every directory is only listed once, which also stops at
+/// links to one of its ancestors.
+fn subdirs(dir: &Path, dirs: &mut Vec<PathBuf>, visited: &mut HashSet<PathBuf>) {
+    if !dir.canonicalize().is_ok_and(|dir| visited.insert(dir)) {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut children: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    children.sort();
+    for child in children {
+        if child.canonicalize().is_ok_and(|c| !visited.contains(&c)) {
+            dirs.push(child.clone());
+            subdirs(&child, dirs, visited);
+        }
+    }
+}
+
+/// Resolves the value of a path attribute relative to `base`. A value ending
+/// in `**` denotes the directory and all its subdirectories.
+pub(crate) fn resolve(base: &Path, value: &str) -> Vec<PathBuf> {
+    match value.strip_suffix("**") {
+        Some(prefix) => {
+            let root = normalize(&base.join(prefix));
+            let mut dirs = vec![root.clone()];
+            subdirs(&root, &mut dirs, &mut HashSet::new());
+            dirs
+        }
+        None => vec![normalize(&base.join(value))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/prj/./obj/../lib")),
+            PathBuf::from("/prj/lib")
+        );
+        assert_eq!(
+            normalize(Path::new("/../gpr_rust_missing")),
+            PathBuf::from("/gpr_rust_missing")
+        );
+        assert_eq!(normalize(Path::new("../lib")), PathBuf::from("../lib"));
+        let testdata = Path::new("testdata").canonicalize().unwrap();
+        assert_eq!(
+            normalize(&testdata.join("src/../missing/./obj/..")),
+            testdata.join("missing")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_normalize_symlink() {
+        let dir = std::env::temp_dir().join(format!("gpr_rust_normalize_{}", std::process::id()));
+        fs::create_dir_all(dir.join("real/prj")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let link = dir.join("link");
+        if !link.exists() {
+            std::os::unix::fs::symlink(dir.join("real/prj"), &link).unwrap();
+        }
+        assert_eq!(normalize(&link.join("../lib")), dir.join("real/lib"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_symlink_cycle() {
+        let dir = std::env::temp_dir().join(format!("gpr_rust_cycle_{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/a")).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let link = dir.join("src/a/up");
+        if !link.exists() {
+            std::os::unix::fs::symlink(dir.join("src"), &link).unwrap();
+        }
+        assert_eq!(
+            resolve(&dir, "src/**"),
+            vec![dir.join("src"), dir.join("src/a")]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = Path::new("/prj");
+        assert_eq!(resolve(base, "src"), vec![PathBuf::from("/prj/src")]);
+        assert_eq!(resolve(base, "/opt/src"), vec![PathBuf::from("/opt/src")]);
+        let base = Path::new("testdata");
+        let dirs = resolve(base, "ali/**");
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("testdata/ali"),
+                PathBuf::from("testdata/ali/sequential"),
+                PathBuf::from("testdata/ali/tasking"),
+            ]
+        );
+    }
+}
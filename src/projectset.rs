@@ -0,0 +1,141 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{
+    error,
+    gprgen::{Expression, ProjectFile, ProjectKind},
+    link, Configuration, Directive, Library, LinkOptions, Project, Scenario,
+};
+
+/// Name of the generated aggregate project.
+const AGGREGATE: &str = "Gpr_Rust_Set";
+
+/// Several independent projects that are built together with a single
+/// gprbuild invocation through a generated aggregate project. Projects
+/// shared by the members are only built once.
+#[derive(Debug)]
+pub struct ProjectSet {
+    aggregate: Project,
+    members: Vec<Project>,
+}
+
+impl ProjectSet {
+    /// Writes an aggregate project of `files` to `dir` and loads it together
+    /// with all members.
+    pub fn new(files: &[&Path], dir: &Path) -> Result<ProjectSet, error::Error> {
+        ProjectSet::load_with_config(files, dir, &Scenario::new(), &Configuration::Auto)
+    }
+
+    /// Like [`ProjectSet::new`] but loads the aggregate and all members with
+    /// `scenario` and `config`.
+    pub fn load_with_config(
+        files: &[&Path],
+        dir: &Path,
+        scenario: &Scenario,
+        config: &Configuration,
+    ) -> Result<ProjectSet, error::Error> {
+        let files = files
+            .iter()
+            .map(|file| file.canonicalize())
+            .collect::<Result<Vec<PathBuf>, _>>()?;
+        let project_files = Expression::List(
+            files
+                .iter()
+                .map(|file| Expression::from(file.display().to_string()))
+                .collect(),
+        );
+        let file = ProjectFile::new(AGGREGATE, ProjectKind::Aggregate)
+            .comment("Project set generated by gpr.")
+            .attribute("Project_Files", project_files)
+            .write(dir)?;
+        let aggregate = Project::load_with_config(&file, scenario, config)?;
+        let members = files
+            .iter()
+            .map(|file| Project::load_with_config(file, scenario, config))
+            .collect::<Result<_, _>>()?;
+        Ok(ProjectSet { aggregate, members })
+    }
+
+    /// The generated aggregate project.
+    pub fn aggregate(&self) -> &Project {
+        &self.aggregate
+    }
+
+    pub fn members(&self) -> &[Project] {
+        &self.members
+    }
+
+    pub fn gprbuild_args(&self) -> Result<Vec<OsString>, error::Error> {
+        self.aggregate.gprbuild_args()
+    }
+
+    /// Builds all members in one parallel gprbuild run, `args` are passed
+    /// on to gprbuild.
+    pub fn build(&self, args: &[&str]) -> Result<(), error::Error> {
+        let result = Command::new("gprbuild")
+            .args(self.gprbuild_args()?)
+            .args(["-p", "-j0"])
+            .args(args)
+            .output()?;
+        if !result.status.success() {
+            return Err(error::Error::command(
+                "gprbuild",
+                &String::from_utf8_lossy(&result.stderr),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the libraries of all library projects of the set, projects
+    /// shared by several members are only included once.
+    pub fn libraries(&self) -> Result<Vec<Library>, error::Error> {
+        let mut libraries: Vec<Library> = Vec::new();
+        for member in &self.members {
+            for library in member.libraries()? {
+                if !libraries.iter().any(|l| l.project == library.project) {
+                    libraries.push(library);
+                }
+            }
+        }
+        Ok(libraries)
+    }
+
+    /// Copies the dynamic libraries of all members next to the binaries of
+    /// the current cargo profile, see [`Project::copy_dynamic_libraries`].
+    pub fn copy_dynamic_libraries(&self, options: &LinkOptions) -> Result<(), error::Error> {
+        for member in &self.members {
+            member.copy_dynamic_libraries(options)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the link directives of all members. The libraries of all
+    /// members are linked first, followed by the GNAT runtime, which is
+    /// linked with tasking support if any member requires it.
+    pub fn link_directives(&self, options: &LinkOptions) -> Result<Vec<Directive>, error::Error> {
+        let mut linked: Vec<Library> = Vec::new();
+        let mut tasking = false;
+        for member in &self.members {
+            for library in member.linked_libraries()? {
+                if !linked.iter().any(|l| l.project == library.project) {
+                    linked.push(library);
+                }
+            }
+            tasking |= member.uses_tasking()?;
+        }
+        let adalib = match (options.runtime_kind(), self.members.first()) {
+            (Some(_), Some(member)) => Some(member.runtime_dir()?),
+            _ => None,
+        };
+        Ok(link::directives(
+            &linked,
+            &self.libraries()?,
+            adalib.as_deref(),
+            tasking,
+            options,
+        ))
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error,
+    gprgen::{self, Expression, Package, ProjectFile, ProjectKind},
+    link, LibraryKind,
+};
+
+/// Externally built GPR library project wrapping a Rust `staticlib` or
+/// `cdylib`, so Ada projects can `with` and link the Rust library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustLibrary {
+    name: String,
+    dir: PathBuf,
+    kind: LibraryKind,
+    source_dirs: Vec<PathBuf>,
+    linker_options: Vec<String>,
+}
+
+/// Extracts the linker flags from the `native-static-libs` note rustc prints
+/// for static libraries with `--print native-static-libs`.
+pub fn native_static_libs(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once("native-static-libs:"))
+        .flat_map(|(_, flags)| flags.split_whitespace())
+        .map(String::from)
+        .collect()
+}
+
+impl RustLibrary {
+    /// Library `lib<name>.a` (static) or `lib<name>.so` (dynamic) located in
+    /// `dir`. Dashes in the crate name are replaced the same way cargo does.
+    pub fn new(name: &str, dir: &Path, kind: LibraryKind) -> RustLibrary {
+        RustLibrary {
+            name: name.replace('-', "_"),
+            dir: dir.to_path_buf(),
+            kind,
+            source_dirs: Vec::new(),
+            linker_options: Vec::new(),
+        }
+    }
+
+    /// Library of the crate whose build script is currently running, built
+    /// into the directory of the current cargo profile.
+    pub fn from_build_env(kind: LibraryKind) -> Result<RustLibrary, error::Error> {
+        let name =
+            env::var("CARGO_PKG_NAME").map_err(|_| error::Error::no_build_env("CARGO_PKG_NAME"))?;
+        Ok(RustLibrary::new(&name, &link::profile_dir()?, kind))
+    }
+
+    /// Directory with Ada sources that are part of the library, e.g. the
+    /// specs generated by [`Project::add_ada_bindings`](super::Project::add_ada_bindings).
+    pub fn source_dir(mut self, dir: &Path) -> RustLibrary {
+        self.source_dirs.push(dir.to_path_buf());
+        self
+    }
+
+    /// Options passed to the linker of every executable that depends on the
+    /// library, e.g. the result of [`native_static_libs`].
+    pub fn linker_options<S: AsRef<str>>(mut self, options: &[S]) -> RustLibrary {
+        self.linker_options
+            .extend(options.iter().map(|o| String::from(o.as_ref())));
+        self
+    }
+
+    pub fn project_name(&self) -> String {
+        gprgen::ada_name(&self.name)
+    }
+
+    /// Returns the project file describing the library.
+    pub fn project(&self) -> ProjectFile {
+        let path = |dir: &PathBuf| Expression::from(dir.display().to_string());
+        let mut project = ProjectFile::new(&self.project_name(), ProjectKind::Library)
+            .comment(&format!(
+                "Project for the Rust library `{}`, generated by gpr.",
+                self.name
+            ))
+            .attribute("Externally_Built", "true")
+            .attribute(
+                "Languages",
+                if self.source_dirs.is_empty() {
+                    Expression::List(Vec::new())
+                } else {
+                    Expression::from(["Ada"])
+                },
+            )
+            .attribute(
+                "Source_Dirs",
+                Expression::List(self.source_dirs.iter().map(path).collect()),
+            )
+            .attribute("Library_Name", self.name.as_str())
+            .attribute("Library_Dir", path(&self.dir))
+            .attribute(
+                "Library_Kind",
+                match self.kind {
+                    LibraryKind::Static => "static",
+                    LibraryKind::Dynamic => "relocatable",
+                },
+            );
+        if !self.linker_options.is_empty() {
+            project = project.package(
+                Package::new("Linker").attribute(
+                    "Linker_Options",
+                    Expression::List(
+                        self.linker_options
+                            .iter()
+                            .map(|o| Expression::from(o.as_str()))
+                            .collect(),
+                    ),
+                ),
+            );
+        }
+        project
+    }
+
+    /// Writes the project file to `<dir>/<name>.gpr`.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, error::Error> {
+        let file = dir.join(format!("{}.gpr", self.name));
+        std::fs::write(&file, self.project().to_string())?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let library = RustLibrary::new("rust-math", Path::new("/target/release"), LibraryKind::Static)
+            .source_dir(Path::new("/obj/gpr-rust"))
+            .linker_options(&native_static_libs(
+                "note: Link against the following native artifacts when linking against this static library.\n\
+                 note: native-static-libs: -lgcc_s -lutil -lc\n",
+            ));
+        assert_eq!(
+            library.project().to_string(),
+            "--  Project for the Rust library `rust_math`, generated by gpr.
+
+library project Rust_Math is
+
+   for Externally_Built use \"true\";
+   for Languages use (\"Ada\");
+   for Source_Dirs use (\"/obj/gpr-rust\");
+   for Library_Name use \"rust_math\";
+   for Library_Dir use \"/target/release\";
+   for Library_Kind use \"static\";
+
+   package Linker is
+      for Linker_Options use (\"-lgcc_s\", \"-lutil\", \"-lc\");
+   end Linker;
+
+end Rust_Math;
+"
+        );
+    }
+}
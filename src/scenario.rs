@@ -0,0 +1,670 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    ast::{self, Expr, Item, ItemKind, Term},
+    error, parser, Project,
+};
+
+#[derive(Debug, Clone)]
+enum Entry {
+    External {
+        name: String,
+        value: String,
+    },
+    Feature {
+        feature: String,
+        name: String,
+        enabled: String,
+        disabled: Option<String>,
+    },
+}
+
+/// Set of scenario variables (`-X` externals) used to load and build a project.
+///
+/// Values can either be set explicitly or derived from the Cargo features of
+/// the crate whose build script is running. Entries are applied in the order
+/// they were added, later entries override earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    entries: Vec<Entry>,
+}
+
+fn feature_env(feature: &str) -> String {
+    format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))
+}
+
+impl Scenario {
+    pub fn new() -> Scenario {
+        Scenario::default()
+    }
+
+    /// Sets the external `name` to `value` unconditionally.
+    pub fn external(mut self, name: &str, value: &str) -> Scenario {
+        self.entries.push(Entry::External {
+            name: String::from(name),
+            value: String::from(value),
+        });
+        self
+    }
+
+    /// Sets the external `name` to `enabled` if the Cargo feature `feature` is
+    /// active and to `disabled` otherwise. If `disabled` is `None` the external
+    /// is left untouched when the feature is not active.
+    pub fn feature(
+        mut self,
+        feature: &str,
+        name: &str,
+        enabled: &str,
+        disabled: Option<&str>,
+    ) -> Scenario {
+        self.entries.push(Entry::Feature {
+            feature: String::from(feature),
+            name: String::from(name),
+            enabled: String::from(enabled),
+            disabled: disabled.map(String::from),
+        });
+        self
+    }
+
+    /// Resolves the scenario using the `CARGO_FEATURE_*` variables of the
+    /// current build script environment.
+    pub fn context(&self) -> BTreeMap<String, String> {
+        self.context_with(|feature| env::var_os(feature_env(feature)).is_some())
+    }
+
+    fn context_with<F: Fn(&str) -> bool>(&self, enabled: F) -> BTreeMap<String, String> {
+        let mut context = BTreeMap::new();
+        for entry in &self.entries {
+            let (name, value) = match entry {
+                Entry::External { name, value } => (name, value),
+                Entry::Feature {
+                    feature,
+                    name,
+                    enabled: value,
+                    ..
+                } if enabled(feature) => (name, value),
+                Entry::Feature {
+                    name,
+                    disabled: Some(value),
+                    ..
+                } => (name, value),
+                Entry::Feature { .. } => continue,
+            };
+            context.insert(name.clone(), value.clone());
+        }
+        context
+    }
+}
+
+#[derive(Debug)]
+struct ExternalRef {
+    external: String,
+    variable: Option<String>,
+    type_name: Option<String>,
+    default: Option<String>,
+}
+
+/// Calls `f` for `items` and all items nested in packages and case
+/// alternatives.
+fn walk<'a>(items: &'a [Item], f: &mut impl FnMut(&'a Item)) {
+    for item in items {
+        f(item);
+        match &item.kind {
+            ItemKind::Package { items, .. } => walk(items, f),
+            ItemKind::Case { alternatives, .. } => {
+                for alternative in alternatives {
+                    walk(&alternative.items, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Value of an expression that consists of a single string literal.
+fn literal(expr: &Expr) -> Option<String> {
+    match expr.terms.as_slice() {
+        [Term::String(literal)] => Some(literal.value.clone()),
+        _ => None,
+    }
+}
+
+/// Collects the references to externals in `expr`.
+fn external_refs(expr: &Expr, refs: &mut Vec<ExternalRef>) {
+    for term in &expr.terms {
+        match term {
+            Term::List(exprs, _) => {
+                for expr in exprs {
+                    external_refs(expr, refs);
+                }
+            }
+            Term::Call {
+                function,
+                arguments,
+            } => {
+                let function = function.to_string().to_lowercase();
+                if let (true, Some(external)) = (
+                    function == "external" || function == "external_as_list",
+                    arguments.first().and_then(literal),
+                ) {
+                    refs.push(ExternalRef {
+                        external,
+                        variable: None,
+                        type_name: None,
+                        default: arguments
+                            .get(1)
+                            .filter(|_| function == "external")
+                            .and_then(literal),
+                    });
+                }
+                for argument in arguments {
+                    external_refs(argument, refs);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parsed project file.
+#[derive(Debug)]
+struct Parsed {
+    path: PathBuf,
+    ast: ast::ProjectFile,
+}
+
+impl Parsed {
+    fn parse(path: &Path) -> Result<Parsed, error::Error> {
+        Ok(Parsed {
+            path: path.to_path_buf(),
+            ast: parser::parse(&fs::read_to_string(path)?, path)?,
+        })
+    }
+
+    fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Projects named in the `with` and `extends` clauses and in
+    /// `Project_Files`.
+    fn dependencies(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .ast
+            .withs
+            .iter()
+            .flat_map(|with| with.paths.iter().map(|path| path.value.clone()))
+            .chain(self.ast.extends.iter().map(|e| e.path.value.clone()))
+            .collect();
+        walk(&self.ast.items, &mut |item| {
+            if let ItemKind::Attribute { name, value, .. } = &item.kind {
+                if name.last().eq_ignore_ascii_case("project_files") {
+                    let mut refs = Vec::new();
+                    collect_literals(value, &mut refs);
+                    names.extend(refs);
+                }
+            }
+        });
+        names
+    }
+
+    /// Looks up the typed string type `name` declared in this project.
+    fn find_type(&self, name: &str) -> Option<Vec<String>> {
+        let mut found = None;
+        walk(&self.ast.items, &mut |item| {
+            if let ItemKind::Type {
+                name: type_name,
+                values,
+            } = &item.kind
+            {
+                if found.is_none() && type_name.last().eq_ignore_ascii_case(name) {
+                    found = Some(values.iter().map(|v| v.value.clone()).collect());
+                }
+            }
+        });
+        found
+    }
+
+    /// References to externals in all declarations, including those in
+    /// inactive case alternatives.
+    fn externals(&self) -> Vec<ExternalRef> {
+        let mut refs = Vec::new();
+        walk(&self.ast.items, &mut |item| match &item.kind {
+            ItemKind::Variable {
+                name,
+                type_name,
+                value,
+            } => {
+                let start = refs.len();
+                external_refs(value, &mut refs);
+                let initializer = matches!(value.terms.first(), Some(Term::Call { .. }));
+                if let (true, Some(reference)) = (initializer, refs.get_mut(start)) {
+                    reference.variable = Some(name.last().to_owned());
+                    reference.type_name = type_name.as_ref().map(ToString::to_string);
+                }
+            }
+            ItemKind::Attribute { value, .. } => external_refs(value, &mut refs),
+            _ => {}
+        });
+        refs
+    }
+}
+
+/// Collects the string literals in `expr`.
+fn collect_literals(expr: &Expr, values: &mut Vec<String>) {
+    for term in &expr.terms {
+        match term {
+            Term::String(literal) => values.push(literal.value.clone()),
+            Term::List(exprs, _) => {
+                for expr in exprs {
+                    collect_literals(expr, values);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Locates a project file referenced in a `with` or `extends` clause the same
+/// way gprbuild does: relative to the referencing project first, then along
+/// `GPR_PROJECT_PATH` and `ADA_PROJECT_PATH`.
+pub(crate) fn resolve_project_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut file = PathBuf::from(name);
+    if file.extension().is_none() {
+        file.set_extension("gpr");
+    }
+    let mut candidates = vec![dir.join(&file)];
+    for var in ["GPR_PROJECT_PATH", "ADA_PROJECT_PATH"] {
+        if let Some(paths) = env::var_os(var) {
+            candidates.extend(env::split_paths(&paths).map(|p| p.join(&file)));
+        }
+    }
+    candidates
+        .into_iter()
+        .find(|c| c.is_file())
+        .and_then(|c| c.canonicalize().ok())
+}
+
+/// External referenced by a project tree, as it can be set with `-X`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioVariable {
+    /// Name of the external.
+    pub name: String,
+    /// Project variable initialized from the external.
+    pub variable: Option<String>,
+    /// Typed string type of the variable initialized from the external.
+    pub type_name: Option<String>,
+    /// Legal values of the external, `None` if the external is untyped and
+    /// accepts any value. If the external initializes variables of several
+    /// types only the values legal for all of them are listed.
+    pub values: Option<Vec<String>>,
+    /// Value used if the external is not set.
+    pub default: Option<String>,
+    /// Project file declaring the type of the variable, or the first project
+    /// referencing the external if it is untyped.
+    pub project: PathBuf,
+    /// Value the external has in a loaded project.
+    pub value: Option<String>,
+}
+
+impl ScenarioVariable {
+    pub fn is_valid(&self, value: &str) -> bool {
+        self.values
+            .as_ref()
+            .is_none_or(|values| values.iter().any(|v| v == value))
+    }
+}
+
+/// Returns all scenario variables referenced by the project `file` and the
+/// projects it depends on without loading the project.
+pub fn scenario_variables(file: &Path) -> Result<Vec<ScenarioVariable>, error::Error> {
+    Ok(Declarations::scan(file)?.variables())
+}
+
+/// Project loaded with one combination of scenario variable values.
+#[derive(Debug)]
+pub struct Combination {
+    pub context: BTreeMap<String, String>,
+    pub project: Result<Project, error::Error>,
+}
+
+/// Combination of scenario variable values for which loading the project
+/// failed.
+#[derive(Debug)]
+pub struct Failure {
+    pub context: BTreeMap<String, String>,
+    pub error: error::Error,
+}
+
+/// Iterator over all legal combinations of typed scenario variables of a
+/// project. Externals that are not part of the iteration keep their default
+/// value.
+#[derive(Debug)]
+pub struct Combinations {
+    file: PathBuf,
+    variables: Vec<(String, Vec<String>)>,
+    counters: Option<Vec<usize>>,
+}
+
+impl Combinations {
+    /// Iterates over the typed scenario variables of `file`. If `only` is set
+    /// the iteration is restricted to the named externals.
+    pub fn new(file: &Path, only: Option<&[&str]>) -> Result<Combinations, error::Error> {
+        let typed: Vec<(String, Vec<String>)> = scenario_variables(file)?
+            .into_iter()
+            .filter_map(|v| Some((v.name, v.values?)))
+            .collect();
+        let variables = match only {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    typed
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                        .cloned()
+                        .ok_or_else(|| error::Error::unknown_scenario_variable(file, name))
+                })
+                .collect::<Result<_, _>>()?,
+            None => typed,
+        };
+        let counters = if variables.iter().any(|(_, values)| values.is_empty()) {
+            None
+        } else {
+            Some(vec![0; variables.len()])
+        };
+        Ok(Combinations {
+            file: file.to_path_buf(),
+            variables,
+            counters,
+        })
+    }
+
+    fn next_context(&mut self) -> Option<BTreeMap<String, String>> {
+        let counters = self.counters.as_mut()?;
+        let context = self
+            .variables
+            .iter()
+            .zip(counters.iter())
+            .map(|((name, values), &c)| (name.clone(), values[c].clone()))
+            .collect();
+        let mut done = true;
+        for (counter, (_, values)) in counters.iter_mut().zip(&self.variables).rev() {
+            *counter += 1;
+            if *counter < values.len() {
+                done = false;
+                break;
+            }
+            *counter = 0;
+        }
+        if done {
+            self.counters = None;
+        }
+        Some(context)
+    }
+
+    /// Loads every combination and returns those that failed.
+    pub fn failures(self) -> Vec<Failure> {
+        self.filter_map(|c| match c.project {
+            Ok(_) => None,
+            Err(error) => Some(Failure {
+                context: c.context,
+                error,
+            }),
+        })
+        .collect()
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Combination;
+
+    fn next(&mut self) -> Option<Combination> {
+        let context = self.next_context()?;
+        let scenario = context
+            .iter()
+            .fold(Scenario::new(), |s, (name, value)| s.external(name, value));
+        Some(Combination {
+            project: Project::load_with_scenario(&self.file, &scenario),
+            context,
+        })
+    }
+}
+
+/// Scenario variable declarations of a project and all projects it depends
+/// on, extracted directly from the project sources.
+#[derive(Debug)]
+pub(crate) struct Declarations {
+    projects: Vec<Parsed>,
+}
+
+impl Declarations {
+    pub fn scan(file: &Path) -> Result<Declarations, error::Error> {
+        let mut projects = vec![Parsed::parse(&file.canonicalize()?)?];
+        let mut visited: HashSet<PathBuf> = projects.iter().map(|p| p.path.clone()).collect();
+        let mut next = 0;
+        while next < projects.len() {
+            let deps: Vec<PathBuf> = projects[next]
+                .dependencies()
+                .iter()
+                .filter_map(|name| resolve_project_path(projects[next].dir(), name))
+                .collect();
+            for dep in deps {
+                if visited.insert(dep.clone()) {
+                    // Errors in dependencies are reported when the project
+                    // is loaded.
+                    if let Ok(project) = Parsed::parse(&dep) {
+                        projects.push(project);
+                    }
+                }
+            }
+            next += 1;
+        }
+        Ok(Declarations { projects })
+    }
+
+    fn find_project(&self, name: &str) -> Option<&Parsed> {
+        self.projects
+            .iter()
+            .find(|p| p.ast.name.to_string().eq_ignore_ascii_case(name))
+    }
+
+    /// Looks up a typed string type and returns its values together with the
+    /// project declaring it.
+    fn find_type<'a>(
+        &'a self,
+        project: &'a Parsed,
+        type_name: &str,
+    ) -> Option<(&'a Parsed, Vec<String>)> {
+        let (project, type_name) = match type_name.rsplit_once('.') {
+            Some((prj, name)) => (self.find_project(prj)?, name),
+            None => (project, type_name),
+        };
+        project
+            .find_type(type_name)
+            .map(|values| (project, values))
+            .or_else(|| {
+                let extends = project.ast.extends.as_ref()?;
+                let extended = resolve_project_path(project.dir(), &extends.path.value)?;
+                let extended = self.projects.iter().find(|p| p.path == extended)?;
+                self.find_type(extended, type_name)
+            })
+    }
+
+    /// Merges all references to the same external into a single variable.
+    pub fn variables(&self) -> Vec<ScenarioVariable> {
+        let mut variables: Vec<ScenarioVariable> = Vec::new();
+        for project in &self.projects {
+            for reference in project.externals() {
+                let declaration = reference
+                    .type_name
+                    .as_ref()
+                    .and_then(|t| self.find_type(project, t));
+                let index = match variables
+                    .iter()
+                    .position(|v| v.name.eq_ignore_ascii_case(&reference.external))
+                {
+                    Some(index) => index,
+                    None => {
+                        variables.push(ScenarioVariable {
+                            name: reference.external.clone(),
+                            variable: None,
+                            type_name: None,
+                            values: None,
+                            default: None,
+                            project: project.path.clone(),
+                            value: None,
+                        });
+                        variables.len() - 1
+                    }
+                };
+                let variable = &mut variables[index];
+                if variable.default.is_none() {
+                    variable.default = reference.default.clone();
+                }
+                if variable.variable.is_none() {
+                    variable.variable = reference.variable.clone();
+                }
+                if let Some((declaring, values)) = declaration {
+                    variable.values = Some(match variable.values.take() {
+                        Some(known) => known.into_iter().filter(|v| values.contains(v)).collect(),
+                        None => {
+                            variable.variable = reference.variable.clone();
+                            variable.type_name = reference.type_name.clone();
+                            variable.project = declaring.path.clone();
+                            values
+                        }
+                    });
+                }
+            }
+        }
+        variables
+    }
+
+    /// Checks that every value in `context` is legal for each typed variable
+    /// initialized from the corresponding external.
+    pub fn validate(&self, context: &BTreeMap<String, String>) -> Result<(), error::Error> {
+        for variable in self.variables() {
+            let Some((name, value)) = context
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&variable.name))
+            else {
+                continue;
+            };
+            if let (false, Some(values)) = (variable.is_valid(value), &variable.values) {
+                return Err(error::Error::invalid_scenario_value(
+                    &variable.project,
+                    name,
+                    value,
+                    values,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_context() {
+        let scenario = Scenario::new()
+            .external("BUILD", "debug")
+            .feature("tls", "ENABLE_TLS", "yes", Some("no"))
+            .feature("fast-math", "BUILD", "release", None);
+        let context = scenario.context_with(|_| false);
+        assert_eq!(context["ENABLE_TLS"], "no");
+        assert_eq!(context["BUILD"], "debug");
+        let context = scenario.context_with(|f| f == "fast-math");
+        assert_eq!(context["ENABLE_TLS"], "no");
+        assert_eq!(context["BUILD"], "release");
+        assert_eq!(feature_env("fast-math"), "CARGO_FEATURE_FAST_MATH");
+        let scenario = scenario.external("ENABLE_TLS", "forced");
+        let context = scenario.context_with(|_| true);
+        assert_eq!(context["ENABLE_TLS"], "forced");
+        assert_eq!(context["BUILD"], "release");
+    }
+
+    #[test]
+    fn test_validate() {
+        let decls = Declarations::scan(Path::new("testdata/scenario.gpr")).unwrap();
+        let mut context = BTreeMap::new();
+        context.insert(String::from("ENABLE_TLS"), String::from("yes"));
+        assert!(decls.validate(&context).is_ok());
+        context.insert(String::from("ENABLE_TLS"), String::from("maybe"));
+        assert!(matches!(
+            decls.validate(&context),
+            Err(error::Error::InvalidScenarioValue { .. })
+        ));
+        context.insert(String::from("ENABLE_TLS"), String::from("no"));
+        context.insert(String::from("SCENARIO_MODE"), String::from("fast"));
+        assert!(decls.validate(&context).is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let variables = scenario_variables(Path::new("testdata/scenario.gpr")).unwrap();
+        assert_eq!(variables.len(), 3);
+        assert_eq!(variables[0].name, "ENABLE_TLS");
+        assert_eq!(variables[0].variable.as_deref(), Some("Tls"));
+        assert_eq!(variables[0].type_name.as_deref(), Some("Tls_Type"));
+        assert_eq!(
+            variables[0].values,
+            Some(vec![String::from("yes"), String::from("no")])
+        );
+        assert_eq!(variables[0].default.as_deref(), Some("no"));
+        assert_eq!(
+            variables[0].project,
+            Path::new("testdata/scenario.gpr").canonicalize().unwrap()
+        );
+        assert_eq!(variables[1].name, "SCENARIO_MODE");
+        assert!(variables[1].is_valid("release"));
+        assert!(!variables[1].is_valid("fast"));
+        assert_eq!(
+            variables[1].project,
+            Path::new("testdata/scenario_common.gpr")
+                .canonicalize()
+                .unwrap()
+        );
+        assert_eq!(variables[2].name, "SCENARIO_CFLAGS");
+        assert_eq!(variables[2].values, None);
+        assert_eq!(variables[2].default, None);
+        assert!(variables[2].is_valid("-O2 -g"));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let decls = Declarations::scan(Path::new("testdata/aggregate.gpr")).unwrap();
+        assert!(decls.variables().is_empty());
+        assert_eq!(decls.projects.len(), 3);
+    }
+
+    #[test]
+    fn test_combinations() {
+        let file = Path::new("testdata/scenario.gpr");
+        let mut combinations = Combinations::new(file, None).unwrap();
+        let mut contexts = Vec::new();
+        while let Some(context) = combinations.next_context() {
+            contexts.push(format!(
+                "{}/{}",
+                context["ENABLE_TLS"], context["SCENARIO_MODE"]
+            ));
+        }
+        assert_eq!(
+            contexts,
+            vec!["yes/debug", "yes/release", "no/debug", "no/release"]
+        );
+        let mut combinations = Combinations::new(file, Some(&["scenario_mode"])).unwrap();
+        assert_eq!(combinations.next_context().unwrap().len(), 1);
+        assert!(combinations.next_context().is_some());
+        assert!(combinations.next_context().is_none());
+        assert!(matches!(
+            Combinations::new(file, Some(&["SCENARIO_CFLAGS"])),
+            Err(error::Error::UnknownScenarioVariable { .. })
+        ));
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{binding::AttributeValue, error};
+
+/// Spec and body suffixes of the languages gprconfig knows by default.
+const DEFAULT_SUFFIXES: &[(&str, &str, &str)] = &[
+    ("ada", ".ads", ".adb"),
+    ("c", ".h", ".c"),
+    ("c++", ".hh", ".cpp"),
+];
+
+/// Attribute of a view, `None` if it is neither declared nor has a default.
+pub(crate) type Lookup<'a> =
+    dyn Fn(Option<&str>, &str, Option<&str>) -> Option<AttributeValue> + 'a;
+
+fn list(value: Option<AttributeValue>) -> Vec<String> {
+    match value {
+        Some(AttributeValue::Single(value)) => vec![value],
+        Some(AttributeValue::List(values)) => values,
+        None => Vec::new(),
+    }
+}
+
+fn single(value: Option<AttributeValue>) -> Option<String> {
+    match value {
+        Some(AttributeValue::Single(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Rules selecting the source files of a project among the files in its
+/// source directories: the naming scheme of its languages, `Source_Files`
+/// or `Source_List_File` and `Excluded_Source_Files`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Rules {
+    suffixes: Vec<String>,
+    /// File names given in `Naming'Spec` and `Naming'Body`.
+    named: Vec<String>,
+    /// Explicit list of source files, if the project has one.
+    files: Option<Vec<String>>,
+    excluded: Vec<String>,
+}
+
+impl Rules {
+    /// Reads the rules from the attributes of a project in `dir`.
+    pub fn new(dir: &Path, attr: &Lookup) -> Result<Rules, error::Error> {
+        let languages = match attr(None, "languages", None) {
+            Some(value) => list(Some(value)),
+            None => vec![String::from("Ada")],
+        };
+        let mut rules = Rules::default();
+        for language in languages.iter().map(|l| l.to_lowercase()) {
+            let default = DEFAULT_SUFFIXES.iter().find(|(l, _, _)| *l == language);
+            let suffix = |names: &[&str], default: Option<&str>| {
+                names
+                    .iter()
+                    .find_map(|name| single(attr(Some("naming"), name, Some(&language))))
+                    .or(default.map(String::from))
+            };
+            rules.suffixes.extend(suffix(
+                &["spec_suffix", "specification_suffix"],
+                default.map(|(_, spec, _)| *spec),
+            ));
+            rules.suffixes.extend(suffix(
+                &["body_suffix", "implementation_suffix"],
+                default.map(|(_, _, body)| *body),
+            ));
+            if language == "ada" {
+                rules.suffixes.extend(suffix(&["separate_suffix"], None));
+            }
+        }
+        rules.suffixes.retain(|s| !s.is_empty());
+        rules.suffixes.dedup();
+        rules.files = match (
+            attr(None, "source_files", None),
+            single(attr(None, "source_list_file", None)),
+        ) {
+            (Some(files), _) => Some(list(Some(files))),
+            (None, Some(file)) => Some(
+                fs::read_to_string(dir.join(file))?
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with("--"))
+                    .map(String::from)
+                    .collect(),
+            ),
+            (None, None) => None,
+        };
+        rules.excluded = ["excluded_source_files", "locally_removed_files"]
+            .iter()
+            .flat_map(|name| list(attr(None, name, None)))
+            .collect();
+        Ok(rules)
+    }
+
+    /// Adds a file named in the `Spec` or `Body` attribute of `Naming`.
+    pub fn name(&mut self, file: &str) {
+        self.named.push(String::from(file));
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        (self.named.iter().any(|n| n == name)
+            || self.suffixes.iter().any(|s| name.ends_with(s.as_str())))
+            && self
+                .files
+                .as_ref()
+                .is_none_or(|f| f.iter().any(|f| f == name))
+            && !self.excluded.iter().any(|e| e == name)
+    }
+}
+
+/// Returns the source files in `dirs` selected by `rules`. If several
+/// directories contain a file of the same name, the first one is used.
+pub(crate) fn select(dirs: &[PathBuf], rules: &Rules) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut sources = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+        files.sort();
+        for file in files {
+            let Some(name) = file.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if rules.matches(name) && seen.insert(name.to_owned()) {
+                sources.push(file);
+            }
+        }
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let attr = |pkg: Option<&str>, name: &str, index: Option<&str>| match (pkg, name, index) {
+            (None, "languages", None) => Some(AttributeValue::List(vec![
+                String::from("Ada"),
+                String::from("C"),
+            ])),
+            (Some("naming"), "body_suffix", Some("ada")) => {
+                Some(AttributeValue::Single(String::from(".ada")))
+            }
+            (None, "excluded_source_files", None) => {
+                Some(AttributeValue::List(vec![String::from("old.c")]))
+            }
+            _ => None,
+        };
+        let mut rules = Rules::new(Path::new("."), &attr).unwrap();
+        rules.name("main.2.ada");
+        assert_eq!(rules.suffixes, vec![".ads", ".ada", ".h", ".c"]);
+        assert!(rules.matches("pkg.ada"));
+        assert!(rules.matches("main.2.ada"));
+        assert!(rules.matches("util.c"));
+        assert!(!rules.matches("pkg.adb"));
+        assert!(!rules.matches("old.c"));
+        assert!(!rules.matches("README"));
+    }
+
+    #[test]
+    fn test_select() {
+        let rules = Rules::new(Path::new("."), &|_, _, _| None).unwrap();
+        let sources = select(
+            &[
+                PathBuf::from("testdata/extending/src"),
+                PathBuf::from("testdata/src"),
+            ],
+            &rules,
+        );
+        assert_eq!(
+            sources,
+            vec![
+                PathBuf::from("testdata/extending/src/test2.adb"),
+                PathBuf::from("testdata/src/test2.ads"),
+            ]
+        );
+    }
+}
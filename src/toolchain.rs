@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::error;
+
+/// Knowledge base checked out by the build script of this crate.
+const KNOWLEDGE_BASE: Option<&str> = option_env!("GPR_RUST_KB_DIR");
+
+/// Compiler found in `PATH` by gprconfig.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compiler {
+    pub language: String,
+    /// Normalized target triplet of the compiler.
+    pub target: String,
+    pub executable: String,
+    pub version: String,
+    pub runtimes: Vec<String>,
+    /// Directory containing the compiler executable.
+    pub path: PathBuf,
+    pub native: bool,
+}
+
+/// Keys of the fields printed by `gprconfig --mi-show-compilers`.
+const FIELDS: [&str; 9] = [
+    "target",
+    "normalized_target",
+    "executable",
+    "path",
+    "lang",
+    "version",
+    "runtime",
+    "native",
+    "name",
+];
+
+/// Returns the key of a field starting at the beginning of `text`.
+fn field_key(text: &str) -> Option<&str> {
+    let (key, _) = text.split_once(':')?;
+    FIELDS
+        .iter()
+        .any(|field| field.eq_ignore_ascii_case(key))
+        .then_some(key)
+}
+
+/// Adds the `key:value` pairs of `line` to `fields`. A value extends up to
+/// the next known key, so values like paths may contain spaces.
+fn push_field(fields: &mut HashMap<String, String>, line: &str) {
+    let mut starts: Vec<usize> = line
+        .char_indices()
+        .filter(|&(i, _)| i == 0 || line[..i].ends_with(char::is_whitespace))
+        .map(|(i, _)| i)
+        .filter(|&i| field_key(&line[i..]).is_some())
+        .collect();
+    starts.push(line.len());
+    for window in starts.windows(2) {
+        let field = &line[window[0]..window[1]];
+        if let Some((key, value)) = field.split_once(':') {
+            fields.insert(key.to_lowercase(), String::from(value.trim_end()));
+        }
+    }
+}
+
+/// Parses the output of `gprconfig --mi-show-compilers`. Each compiler
+/// starts with its index followed by `key:value` pairs that may continue on
+/// the following lines.
+fn parse_compilers(output: &str) -> Vec<Compiler> {
+    let mut records: Vec<HashMap<String, String>> = Vec::new();
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        let starts_record = trimmed
+            .split_whitespace()
+            .next()
+            .is_some_and(|w| w.chars().all(|c| c.is_ascii_digit()));
+        if starts_record {
+            records.push(HashMap::new());
+        }
+        if let Some(record) = records.last_mut() {
+            push_field(record, trimmed);
+        }
+    }
+    let mut compilers: Vec<Compiler> = Vec::new();
+    for mut record in records {
+        let mut take = |key: &str| record.remove(key).unwrap_or_default();
+        let language = take("lang");
+        let target = match take("normalized_target") {
+            t if t.is_empty() => take("target"),
+            t => t,
+        };
+        let executable = take("executable");
+        let version = take("version");
+        let runtime = take("runtime");
+        let path = PathBuf::from(take("path"));
+        let native = take("native").eq_ignore_ascii_case("true");
+        if language.is_empty() {
+            continue;
+        }
+        match compilers.iter_mut().find(|c| {
+            c.language == language
+                && c.target == target
+                && c.executable == executable
+                && c.version == version
+                && c.path == path
+        }) {
+            Some(compiler) => {
+                if !runtime.is_empty() && !compiler.runtimes.contains(&runtime) {
+                    compiler.runtimes.push(runtime);
+                }
+            }
+            None => compilers.push(Compiler {
+                language,
+                target,
+                executable,
+                version,
+                runtimes: if runtime.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![runtime]
+                },
+                path,
+                native,
+            }),
+        }
+    }
+    compilers
+}
+
+/// Lists all compilers for `target` that gprconfig finds in `PATH`, or for all
+/// targets if `target` is `None`.
+pub fn compilers(target: Option<&str>) -> Result<Vec<Compiler>, error::Error> {
+    let mut cmd = Command::new("gprconfig");
+    cmd.arg("--batch")
+        .arg("--mi-show-compilers")
+        .arg(format!("--target={}", target.unwrap_or("all")));
+    if let Some(kb) = KNOWLEDGE_BASE.map(Path::new).filter(|kb| kb.is_dir()) {
+        cmd.arg("--db").arg(kb);
+    }
+    let result = match cmd.output() {
+        Ok(result) => result,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(error::Error::command(
+                "gprconfig",
+                "not found in PATH, make sure a GNAT toolchain with gprbuild is installed \
+                 and in PATH (e.g. with `eval $(alr printenv)`)",
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if !result.status.success() {
+        return Err(error::Error::command(
+            "gprconfig",
+            &String::from_utf8_lossy(&result.stderr),
+        ));
+    }
+    Ok(parse_compilers(&String::from_utf8_lossy(&result.stdout)))
+}
+
+/// Returns the first Ada compiler for `target` that provides `runtime`. The
+/// native target and the default runtime are used if not specified.
+pub fn find_ada_compiler(
+    target: Option<&str>,
+    runtime: Option<&str>,
+) -> Result<Compiler, error::Error> {
+    let compilers = compilers(target)?;
+    compilers
+        .iter()
+        .filter(|c| c.language.eq_ignore_ascii_case("ada"))
+        .filter(|c| target.is_some() || c.native)
+        .find(|c| runtime.is_none_or(|r| c.runtimes.iter().any(|cr| cr == r)))
+        .cloned()
+        .ok_or_else(|| error::Error::no_compiler("Ada", target, runtime, &compilers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MI_OUTPUT: &str = "\
+ 1 target:x86_64-linux normalized_target:x86_64-pc-linux-gnu executable:gnatmake path:/opt/gnat/bin/ lang:Ada version:14.2.0 runtime:default native:TRUE
+ 2 target:x86_64-linux normalized_target:x86_64-pc-linux-gnu executable:gnatmake path:/opt/gnat/bin/ lang:Ada version:14.2.0 runtime:light native:TRUE
+ 3 target:x86_64-linux normalized_target:x86_64-pc-linux-gnu executable:gcc
+   path:/opt/gnat/bin/ lang:C version:14.2.0 native:TRUE
+ 4 target:arm-eabi normalized_target:arm-unknown-eabi executable:arm-eabi-gnatmake path:/opt/GNAT Pro/bin/ lang:Ada version:14.2.0 runtime:light-cortex-m4 native:FALSE
+";
+
+    #[test]
+    fn test_parse_compilers() {
+        let compilers = parse_compilers(MI_OUTPUT);
+        assert_eq!(compilers.len(), 3);
+        assert_eq!(compilers[0].language, "Ada");
+        assert_eq!(compilers[0].target, "x86_64-pc-linux-gnu");
+        assert_eq!(compilers[0].runtimes, vec!["default", "light"]);
+        assert_eq!(compilers[0].path, Path::new("/opt/gnat/bin/"));
+        assert!(compilers[0].native);
+        assert_eq!(compilers[1].language, "C");
+        assert_eq!(compilers[1].executable, "gcc");
+        assert!(compilers[1].runtimes.is_empty());
+        assert_eq!(compilers[2].path, Path::new("/opt/GNAT Pro/bin/"));
+        assert_eq!(compilers[2].runtimes, vec!["light-cortex-m4"]);
+        assert!(!compilers[2].native);
+    }
+
+    #[test]
+    fn test_no_compiler_message() {
+        let compilers = parse_compilers(MI_OUTPUT);
+        let message = format!(
+            "{}",
+            error::Error::no_compiler("Ada", Some("arm-eabi"), Some("light"), &compilers)
+        );
+        assert!(message.contains("arm-eabi"));
+        assert!(message.contains("Ada 14.2.0 for x86_64-pc-linux-gnu (default, light)"));
+    }
+}
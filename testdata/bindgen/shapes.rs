@@ -0,0 +1,45 @@
+// Bindings for the Ada library `shapes`, generated by gpr.
+
+/// Shapes.Color
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red = 1,
+    Green = 2,
+    Blue = 4,
+}
+
+/// Shapes.Point
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub x: core::ffi::c_int,
+    pub y: core::ffi::c_int,
+}
+
+/// Shapes.Handle
+#[repr(C)]
+pub struct Handle {
+    _private: [u8; 0],
+}
+
+/// Shapes.Handle_Access
+pub type HandleAccess = *mut Handle;
+
+/// Shapes.Shape
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Shape {
+    pub origin: Point,
+    pub fill: Color,
+    pub data: HandleAccess,
+}
+
+unsafe extern "C" {
+    /// Shapes.Move
+    pub fn shapes_move(s: *mut Shape, dx: core::ffi::c_int, dy: core::ffi::c_int);
+    /// Shapes.Area
+    pub fn shapes_area(s: *const Shape) -> core::ffi::c_double;
+    /// Shapes.Create
+    pub fn shapes_create(fill: Color) -> HandleAccess;
+}
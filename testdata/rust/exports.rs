@@ -0,0 +1,12 @@
+use std::ffi::c_int;
+
+#[repr(C)]
+pub struct Pair {
+    pub first: c_int,
+    pub second: c_int,
+}
+
+#[no_mangle]
+pub extern "C" fn rust_exports_add(pair: Pair) -> c_int {
+    pair.first + pair.second
+}
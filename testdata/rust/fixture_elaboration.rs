@@ -0,0 +1,52 @@
+// Elaboration guard for the Ada library `fixture`, generated by gpr.
+
+unsafe extern "C" {
+    fn fixtureinit();
+    fn fixturefinal();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unelaborated,
+    Elaborated,
+    Finalized,
+}
+
+static STATE: std::sync::Mutex<State> = std::sync::Mutex::new(State::Unelaborated);
+
+/// Shows that the Ada library `fixture` is elaborated.
+///
+/// The library is initialized when the first guard is acquired and stays
+/// initialized until the process exits or [`finalize`] is called.
+#[derive(Debug, Clone, Copy)]
+pub struct Guard {
+    _private: (),
+}
+
+impl Guard {
+    /// Initializes the library if it is not initialized yet. Returns `None`
+    /// once the library has been finalized.
+    pub fn acquire() -> Option<Guard> {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        match *state {
+            State::Unelaborated => {
+                unsafe { fixtureinit() };
+                *state = State::Elaborated;
+            }
+            State::Elaborated => {}
+            State::Finalized => return None,
+        }
+        Some(Guard { _private: () })
+    }
+}
+
+/// Finalizes the Ada library `fixture` if it is elaborated. The library
+/// cannot be used afterwards, so this must only be called once no code of it
+/// runs any more, e.g. at the end of `main`.
+pub fn finalize() {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if *state == State::Elaborated {
+        unsafe { fixturefinal() };
+    }
+    *state = State::Finalized;
+}